@@ -0,0 +1,408 @@
+//! Attribute macros for the apple runtime.
+//!
+//! `#[apple::main]` and `#[apple::test]` turn an `async fn` into a regular one
+//! that builds a `Runtime` and blocks on the body, which does not need to be `Send`.
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Error, Expr, ExprLit, ItemFn, Lit, MetaNameValue, Path, Token};
+
+/// Scheduler flavor requested through `flavor = ".."`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    CurrentThread,
+    MultiThread,
+}
+
+/// Configuration parsed from the attribute arguments.
+struct Config {
+    flavor: Flavor,
+    worker_threads: Option<usize>,
+    timeout_ms: Option<u64>,
+    krate: Path,
+}
+
+/// Marks the async `main` function as the entrypoint of the program.
+///
+/// ```ignore
+/// #[apple::main(worker_threads = 4)]
+/// async fn main() {
+///     println!("Hello from the runtime!");
+/// }
+/// ```
+///
+/// Arguments:
+/// - `flavor = "multi_thread" | "current_thread"`, defaults to `multi_thread`.
+/// - `worker_threads = N`, defaults to the available parallelism.
+/// - `crate = "path"`, path to the apple crate, defaults to `::apple`.
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    entry(args, item, false)
+}
+
+/// Marks an async function as a test, run on its own runtime.
+///
+/// ```ignore
+/// #[apple::test(timeout = 5)]
+/// async fn reads_back() {
+///     assert_eq!(1 + 1, 2);
+/// }
+/// ```
+///
+/// Accepts the same arguments as `#[apple::main]`, with `flavor` defaulting
+/// to `current_thread`, and additionally:
+/// - `timeout = SECS` or `timeout_ms = MILLIS`, fails the test with a task dump
+///   of its runtime if it did not finish in time.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    entry(args, item, true)
+}
+
+fn entry(args: TokenStream, item: TokenStream, is_test: bool) -> TokenStream {
+    let input = match syn::parse::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(e) => return token_stream_with_error(item, e),
+    };
+
+    let parser = Punctuated::<MetaNameValue, Token![,]>::parse_terminated;
+    let result = parser
+        .parse(args)
+        .and_then(|args| parse_config(args, is_test))
+        .and_then(|config| expand(input, config, is_test));
+
+    match result {
+        Ok(tokens) => tokens.into(),
+        Err(e) => token_stream_with_error(item, e),
+    }
+}
+
+/// Emits the original item along with the error,
+/// so the user does not get a flood of unrelated errors.
+fn token_stream_with_error(mut item: TokenStream, error: Error) -> TokenStream {
+    item.extend(TokenStream::from(error.into_compile_error()));
+    item
+}
+
+fn parse_config(args: Punctuated<MetaNameValue, Token![,]>, is_test: bool) -> syn::Result<Config> {
+    let mut config = Config {
+        flavor: if is_test {
+            Flavor::CurrentThread
+        } else {
+            Flavor::MultiThread
+        },
+        worker_threads: None,
+        timeout_ms: None,
+        krate: syn::parse_quote!(::apple),
+    };
+
+    for arg in args {
+        let name = match arg.path.get_ident() {
+            Some(ident) => ident.to_string(),
+            None => return Err(Error::new_spanned(&arg.path, "expected an identifier")),
+        };
+
+        match name.as_str() {
+            "flavor" => {
+                config.flavor = match parse_str(&arg.value)?.as_str() {
+                    "current_thread" => Flavor::CurrentThread,
+                    "multi_thread" => Flavor::MultiThread,
+                    _ => {
+                        return Err(Error::new_spanned(
+                            &arg.value,
+                            "expected `current_thread` or `multi_thread`",
+                        ))
+                    }
+                }
+            }
+            "worker_threads" => {
+                let threads: usize = parse_int(&arg.value)?;
+                if threads == 0 {
+                    return Err(Error::new_spanned(
+                        &arg.value,
+                        "`worker_threads` must be greater than 0",
+                    ));
+                }
+                config.worker_threads = Some(threads);
+            }
+            "timeout" if is_test => {
+                let secs: u64 = parse_int(&arg.value)?;
+                let ms = secs.checked_mul(1000).ok_or_else(|| {
+                    Error::new_spanned(&arg.value, "`timeout` is too large to fit in milliseconds")
+                })?;
+                config.timeout_ms = Some(ms);
+            }
+            "timeout_ms" if is_test => config.timeout_ms = Some(parse_int(&arg.value)?),
+            "crate" => config.krate = syn::parse_str(&parse_str(&arg.value)?)?,
+            _ => {
+                return Err(Error::new_spanned(
+                    &arg.path,
+                    format!("unknown attribute argument `{name}`"),
+                ))
+            }
+        }
+    }
+
+    if config.flavor == Flavor::CurrentThread && config.worker_threads.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`worker_threads` can only be used with the `multi_thread` flavor",
+        ));
+    }
+
+    Ok(config)
+}
+
+fn parse_str(value: &Expr) -> syn::Result<String> {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Ok(s.value()),
+        _ => Err(Error::new_spanned(value, "expected a string literal")),
+    }
+}
+
+fn parse_int<N>(value: &Expr) -> syn::Result<N>
+where
+    N: std::str::FromStr,
+    N::Err: std::fmt::Display,
+{
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(i), ..
+        }) => i.base10_parse(),
+        _ => Err(Error::new_spanned(value, "expected an integer literal")),
+    }
+}
+
+fn expand(input: ItemFn, config: Config, is_test: bool) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = input;
+
+    if sig.asyncness.take().is_none() {
+        return Err(Error::new_spanned(
+            sig.fn_token,
+            "the `async` keyword is missing from the function declaration",
+        ));
+    }
+
+    if is_test && !sig.inputs.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            "test functions cannot take arguments",
+        ));
+    }
+
+    let krate = config.krate.to_token_stream();
+    let flavor = match config.flavor {
+        Flavor::CurrentThread => quote!(#krate::runtime::Flavor::CurrentThread),
+        Flavor::MultiThread => quote!(#krate::runtime::Flavor::MultiThread),
+    };
+    let threads = match config.worker_threads {
+        Some(threads) => quote!(#threads),
+        None => quote!(::std::thread::available_parallelism().map_or(1, |n| n.get())),
+    };
+
+    let run = match config.timeout_ms {
+        Some(ms) => quote! {
+            let timeout = ::std::time::Duration::from_millis(#ms);
+            match runtime.block_on_local_timeout(body, timeout) {
+                Ok(output) => output,
+                Err(elapsed) => panic!("test timed out after {:?}\n{}", timeout, elapsed.dump()),
+            }
+        },
+        None => quote!(runtime.block_on_local(body)),
+    };
+
+    let test_attr = if is_test {
+        quote!(#[::core::prelude::v1::test])
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        #test_attr
+        #(#attrs)*
+        #vis #sig {
+            let body = async move #block;
+            let runtime = #krate::runtime::Runtime::new(#flavor, #threads);
+            #run
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, parse_config, Config, Flavor};
+    use super::{ItemFn, MetaNameValue, Parser, Punctuated, ToTokens, Token, TokenStream2};
+    use quote::quote;
+
+    fn config(args: TokenStream2, is_test: bool) -> syn::Result<Config> {
+        let parser = Punctuated::<MetaNameValue, Token![,]>::parse_terminated;
+        parse_config(parser.parse2(args)?, is_test)
+    }
+
+    fn error(args: TokenStream2, is_test: bool) -> String {
+        match config(args, is_test) {
+            Ok(_) => panic!("arguments were accepted"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let main = config(quote!(), false).unwrap();
+        assert!(main.flavor == Flavor::MultiThread);
+        assert_eq!(main.worker_threads, None);
+        assert_eq!(main.timeout_ms, None);
+        assert_eq!(main.krate.to_token_stream().to_string(), ":: apple");
+
+        let test = config(quote!(), true).unwrap();
+        assert!(test.flavor == Flavor::CurrentThread);
+    }
+
+    #[test]
+    fn every_argument() {
+        let config = config(
+            quote!(
+                flavor = "multi_thread",
+                worker_threads = 3,
+                timeout_ms = 250,
+                crate = "my::apple"
+            ),
+            true,
+        )
+        .unwrap();
+
+        assert!(config.flavor == Flavor::MultiThread);
+        assert_eq!(config.worker_threads, Some(3));
+        assert_eq!(config.timeout_ms, Some(250));
+        assert_eq!(config.krate.to_token_stream().to_string(), "my :: apple");
+    }
+
+    #[test]
+    fn timeout_in_seconds() {
+        let config = config(quote!(timeout = 2), true).unwrap();
+        assert_eq!(config.timeout_ms, Some(2000));
+    }
+
+    #[test]
+    fn timeout_overflow() {
+        assert_eq!(
+            error(quote!(timeout = 18446744073709552), true),
+            "`timeout` is too large to fit in milliseconds"
+        );
+    }
+
+    #[test]
+    fn current_thread_flavor() {
+        let config = config(quote!(flavor = "current_thread"), false).unwrap();
+        assert!(config.flavor == Flavor::CurrentThread);
+    }
+
+    #[test]
+    fn unknown_flavor() {
+        assert_eq!(
+            error(quote!(flavor = "single_thread"), false),
+            "expected `current_thread` or `multi_thread`"
+        );
+    }
+
+    #[test]
+    fn zero_worker_threads() {
+        assert_eq!(
+            error(quote!(worker_threads = 0), false),
+            "`worker_threads` must be greater than 0"
+        );
+    }
+
+    #[test]
+    fn worker_threads_on_current_thread() {
+        assert_eq!(
+            error(quote!(worker_threads = 2), true),
+            "`worker_threads` can only be used with the `multi_thread` flavor"
+        );
+    }
+
+    #[test]
+    fn timeout_outside_of_tests() {
+        assert_eq!(
+            error(quote!(timeout = 1), false),
+            "unknown attribute argument `timeout`"
+        );
+        assert_eq!(
+            error(quote!(timeout_ms = 1), false),
+            "unknown attribute argument `timeout_ms`"
+        );
+    }
+
+    #[test]
+    fn wrong_literals() {
+        assert_eq!(
+            error(quote!(flavor = 1), false),
+            "expected a string literal"
+        );
+        assert_eq!(
+            error(quote!(worker_threads = "4"), false),
+            "expected an integer literal"
+        );
+        assert_eq!(
+            error(quote!(timeout = 1.5), true),
+            "expected an integer literal"
+        );
+    }
+
+    #[test]
+    fn unknown_argument() {
+        assert_eq!(
+            error(quote!(threads = 4), false),
+            "unknown attribute argument `threads`"
+        );
+        assert_eq!(error(quote!(a::b = 4), false), "expected an identifier");
+    }
+
+    #[test]
+    fn missing_async() {
+        let input: ItemFn = syn::parse_quote!(
+            fn main() {}
+        );
+        let config = config(quote!(), false).unwrap();
+        match expand(input, config, false) {
+            Ok(_) => panic!("non-async function was accepted"),
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "the `async` keyword is missing from the function declaration"
+            ),
+        }
+    }
+
+    #[test]
+    fn test_with_arguments() {
+        let input: ItemFn = syn::parse_quote!(
+            async fn takes(x: u32) {}
+        );
+        let config = config(quote!(), true).unwrap();
+        match expand(input, config, true) {
+            Ok(_) => panic!("test with arguments was accepted"),
+            Err(e) => assert_eq!(e.to_string(), "test functions cannot take arguments"),
+        }
+    }
+
+    #[test]
+    fn test_expansion() {
+        let input: ItemFn = syn::parse_quote!(
+            async fn works() {}
+        );
+        let config = config(quote!(timeout_ms = 10), true).unwrap();
+        let output = expand(input, config, true).unwrap().to_string();
+
+        assert!(output.contains("# [:: core :: prelude :: v1 :: test]"));
+        assert!(output.contains("Flavor :: CurrentThread"));
+        assert!(output.contains("block_on_local_timeout"));
+    }
+}
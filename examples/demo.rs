@@ -0,0 +1,53 @@
+use apple::io::{AsyncReadExt, AsyncWriteExt};
+use apple::net::TcpStream;
+use apple::runtime::Runtime;
+
+fn sleep_for_n_sec(n: u64) {
+    std::thread::sleep(std::time::Duration::from_secs(n))
+}
+
+#[apple::main(worker_threads = 4)]
+async fn main() {
+    let stream = TcpStream::connect("127.0.0.1:8011")
+        .await
+        .expect("tcp connect fail");
+
+    let (mut reader, mut writer) = stream.into_split();
+
+    let handle1 = Runtime::spawn(async move {
+        for _ in 0..2 {
+            let mut buf = [1u8; 5];
+            let fut = reader.async_read(&mut buf);
+            fut.await.expect("Failed reading!");
+            println!("Buffer after read: {:#?}\n", buf.clone());
+        }
+
+        println!("\nLet's sleep for 1 second and test!");
+        sleep_for_n_sec(1);
+        reader
+    });
+
+    let handle2 = Runtime::spawn(async move {
+        let rbuf = [5, 4, 3, 2, 1];
+
+        for _ in 0..2 {
+            let fut_w = writer.async_write(&rbuf);
+            fut_w.await.expect("Failed writing!");
+        }
+
+        println!("\nLet's sleep for 1 second and test!");
+        sleep_for_n_sec(1);
+        writer
+    });
+
+    let reader = handle1.await;
+    let writer = handle2.await;
+    let _stream = reader.reunite(writer).expect("halves of the same stream");
+
+    println!("Meow 2!")
+
+    // loop {
+    //     println!("I am going to do this each second, haha!");
+    //     sleep_for_n_sec(1)
+    // }
+}
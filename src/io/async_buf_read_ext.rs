@@ -0,0 +1,297 @@
+use crate::io::{AsyncBufRead, OwnedWaiters};
+use futures::Stream;
+use std::future::Future;
+use std::io::{Error, ErrorKind, Result};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Future-returning methods for every `AsyncBufRead`.
+pub trait AsyncBufReadExt: AsyncBufRead {
+    /// Fills the internal buffer if it is empty, returning what is buffered.
+    ///
+    /// An empty buffer means the end of the stream.
+    fn fill_buf(&mut self) -> FillBufFuture<'_, Self>
+    where
+        Self: Unpin,
+    {
+        FillBufFuture {
+            reader: Some(self),
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Marks `amt` bytes of the buffer as read, see `AsyncBufRead::consume`.
+    fn consume(&mut self, amt: usize)
+    where
+        Self: Unpin,
+    {
+        Pin::new(self).consume(amt)
+    }
+
+    /// Reads until `byte` or the end of the stream, appending everything
+    /// including `byte` to `buf`. Returns the amount of bytes read, 0 at the end of the stream.
+    ///
+    /// Cancel safe: the bytes read so far are in `buf` already.
+    fn read_until<'a>(&'a mut self, byte: u8, buf: &'a mut Vec<u8>) -> ReadUntilFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadUntilFuture {
+            reader: self,
+            byte,
+            buf,
+            read: 0,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Reads until a newline or the end of the stream, appending everything
+    /// including the newline to `buf`. Returns the amount of bytes read, 0 at the end of the stream.
+    ///
+    /// Fails with `InvalidData`, leaving `buf` as it was, if the line is not UTF-8.
+    /// Not cancel safe, the part of the line read so far is lost.
+    fn read_line<'a>(&'a mut self, buf: &'a mut String) -> ReadLineFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadLineFuture {
+            reader: self,
+            buf,
+            bytes: Vec::new(),
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Stream of the lines, without their `\n` or `\r\n`.
+    fn lines(self) -> Lines<Self>
+    where
+        Self: Sized + Unpin,
+    {
+        Lines {
+            reader: self,
+            bytes: Vec::new(),
+            waiters: OwnedWaiters::default(),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + ?Sized> AsyncBufReadExt for R {}
+
+/// Appends to `buf` until `byte` or the end of the stream, adding the amount of bytes to `read`.
+fn poll_read_until<R: AsyncBufRead + Unpin + ?Sized>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    byte: u8,
+    buf: &mut Vec<u8>,
+    read: &mut usize,
+) -> Poll<Result<()>> {
+    loop {
+        let available = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+        let (done, used) = match available.iter().position(|b| *b == byte) {
+            Some(i) => (true, i + 1),
+            None => (available.is_empty(), available.len()),
+        };
+
+        buf.extend_from_slice(&available[..used]);
+        Pin::new(&mut *reader).consume(used);
+        *read += used;
+
+        if done {
+            return Poll::Ready(Ok(()));
+        }
+    }
+}
+
+fn invalid_utf8(e: std::string::FromUtf8Error) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+/// Future of `AsyncBufReadExt::fill_buf`.
+pub struct FillBufFuture<'a, R: ?Sized> {
+    /// Taken out once the future completes, since the output borrows it.
+    reader: Option<&'a mut R>,
+    waiters: OwnedWaiters,
+}
+
+impl<'a, R: AsyncBufRead + Unpin + ?Sized> Future for FillBufFuture<'a, R> {
+    type Output = Result<&'a [u8]>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let reader = future.reader.take().expect("polled after completion");
+
+        // The borrow of `reader` can only be handed out once the buffer is filled,
+        // so readiness is checked on a reborrow first.
+        match future
+            .waiters
+            .scope(|| Pin::new(&mut *reader).poll_fill_buf(cx))
+        {
+            Poll::Ready(Ok([])) => return Poll::Ready(Ok(&[])),
+            Poll::Ready(Ok(_)) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => {
+                future.reader = Some(reader);
+                return Poll::Pending;
+            }
+        }
+
+        // Buffered bytes are returned as they are until consumed, so this returns right away.
+        match Pin::new(reader).poll_fill_buf(cx) {
+            Poll::Ready(result) => Poll::Ready(result),
+            Poll::Pending => unreachable!("buffered bytes went missing"),
+        }
+    }
+}
+
+/// Future of `AsyncBufReadExt::read_until`.
+pub struct ReadUntilFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    byte: u8,
+    buf: &'a mut Vec<u8>,
+    read: usize,
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncBufRead + Unpin + ?Sized> Future for ReadUntilFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        ready!(future.waiters.scope(|| poll_read_until(
+            future.reader,
+            cx,
+            future.byte,
+            future.buf,
+            &mut future.read
+        )))?;
+        Poll::Ready(Ok(std::mem::take(&mut future.read)))
+    }
+}
+
+/// Future of `AsyncBufReadExt::read_line`.
+pub struct ReadLineFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut String,
+    /// The line so far, only appended to `buf` once it is all there and valid.
+    bytes: Vec<u8>,
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncBufRead + Unpin + ?Sized> Future for ReadLineFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let mut read = 0;
+        ready!(future.waiters.scope(|| poll_read_until(
+            future.reader,
+            cx,
+            b'\n',
+            &mut future.bytes,
+            &mut read
+        )))?;
+
+        let line = String::from_utf8(std::mem::take(&mut future.bytes)).map_err(invalid_utf8)?;
+        future.buf.push_str(&line);
+        Poll::Ready(Ok(line.len()))
+    }
+}
+
+/// Stream of the lines of an `AsyncBufRead`, see `AsyncBufReadExt::lines`.
+///
+/// Ends at the end of the stream, errors are yielded as they come.
+pub struct Lines<R> {
+    reader: R,
+    /// The line so far.
+    bytes: Vec<u8>,
+    waiters: OwnedWaiters,
+}
+
+impl<R> Lines<R> {
+    /// Hands the reader back, the part of a line read so far is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for Lines<R> {
+    type Item = Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let lines = self.get_mut();
+        let mut read = 0;
+        ready!(lines.waiters.scope(|| poll_read_until(
+            &mut lines.reader,
+            cx,
+            b'\n',
+            &mut lines.bytes,
+            &mut read
+        )))?;
+
+        if lines.bytes.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        let mut line = std::mem::take(&mut lines.bytes);
+        if line.last() == Some(&b'\n') {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+        Poll::Ready(Some(String::from_utf8(line).map_err(invalid_utf8)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::mock::MockReader;
+    use crate::io::{AsyncBufReadExt, BufReader};
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn read_line_with_utf8_split_across_fills() {
+        // Every fill holds 2 bytes, so "é" and "ö" get split in half.
+        let text = "hé\nwörld";
+        let mut reader = BufReader::with_capacity(2, MockReader::new(&[text.as_bytes()]));
+
+        let mut line = String::new();
+        assert_eq!(block_on(reader.read_line(&mut line)).unwrap(), 4);
+        assert_eq!(line, "hé\n");
+
+        let mut line = String::new();
+        assert_eq!(block_on(reader.read_line(&mut line)).unwrap(), 6);
+        assert_eq!(line, "wörld");
+
+        assert_eq!(block_on(reader.read_line(&mut line)).unwrap(), 0);
+        assert_eq!(line, "wörld");
+    }
+
+    #[test]
+    fn read_line_rejects_invalid_utf8() {
+        let mut reader = BufReader::new(MockReader::new(&[b"ok\xff\n"]));
+        let mut line = String::from(">");
+        let err = block_on(reader.read_line(&mut line)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(line, ">");
+    }
+
+    #[test]
+    fn read_until_across_fills() {
+        let mut reader = BufReader::with_capacity(3, MockReader::new(&[b"a,bcdef,g"]));
+        let mut buf = Vec::new();
+        assert_eq!(block_on(reader.read_until(b',', &mut buf)).unwrap(), 2);
+        assert_eq!(block_on(reader.read_until(b',', &mut buf)).unwrap(), 6);
+        assert_eq!(block_on(reader.read_until(b',', &mut buf)).unwrap(), 1);
+        assert_eq!(buf, b"a,bcdef,g");
+    }
+
+    #[test]
+    fn lines_strip_their_ending() {
+        let reader = BufReader::with_capacity(4, MockReader::new(&[b"one\r\ntwo\n\nthree"]));
+        let lines: Vec<String> = block_on(reader.lines().map(Result::unwrap).collect());
+        assert_eq!(lines, ["one", "two", "", "three"]);
+    }
+}
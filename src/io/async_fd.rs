@@ -0,0 +1,265 @@
+use crate::io::reactor::Direction;
+use crate::io::{Reactor, Ready, ReadyEvent, Registration, WaiterId, Wake};
+use mio::unix::SourceFd;
+use mio::Interest;
+use std::future::Future;
+use std::io::{ErrorKind, Result as IoResult};
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Any non-blocking file descriptor, registered in the reactor so its readiness can be awaited.
+///
+/// Reading and writing stay up to the caller, the fd has to be in non-blocking mode already.
+/// Owns `T` and deregisters it before dropping it.
+pub struct AsyncFd<T: AsRawFd> {
+    /// Only `None` while `into_inner` takes it out.
+    inner: Option<T>,
+    registration: Registration,
+}
+
+impl<T: AsRawFd> AsyncFd<T> {
+    /// Registers the fd of `inner` in the reactor, for reading and writing.
+    pub fn new(inner: T) -> IoResult<AsyncFd<T>> {
+        AsyncFd::with_interest(inner, Interest::READABLE | Interest::WRITABLE)
+    }
+
+    /// Registers the fd of `inner` in the reactor, for `interest`.
+    pub fn with_interest(inner: T, interest: Interest) -> IoResult<AsyncFd<T>> {
+        let fd = inner.as_raw_fd();
+        let registration = Registration::new(&mut SourceFd(&fd), interest)?;
+        Ok(AsyncFd {
+            inner: Some(inner),
+            registration,
+        })
+    }
+
+    /// Obtains a reference to the wrapped value.
+    pub fn get_ref(&self) -> &T {
+        self.inner.as_ref().expect("value taken out of AsyncFd")
+    }
+
+    /// Obtains a mutable reference to the wrapped value.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.as_mut().expect("value taken out of AsyncFd")
+    }
+
+    /// Deregisters the fd from the reactor and hands the wrapped value back.
+    pub fn into_inner(mut self) -> IoResult<T> {
+        let inner = self.inner.take().expect("value taken out of AsyncFd");
+        self.registration
+            .deregister(&mut SourceFd(&inner.as_raw_fd()))?;
+        Ok(inner)
+    }
+
+    /// Waits for the fd to become readable.
+    pub fn readable(&self) -> Readiness<'_, T> {
+        Readiness {
+            fd: self,
+            dir: Direction::Read,
+            waiter: None,
+        }
+    }
+
+    /// Waits for the fd to become writable.
+    pub fn writable(&self) -> Readiness<'_, T> {
+        Readiness {
+            fd: self,
+            dir: Direction::Write,
+            waiter: None,
+        }
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd> Drop for AsyncFd<T> {
+    /// Deregisters the fd from the reactor.
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            let _ = self
+                .registration
+                .deregister(&mut SourceFd(&inner.as_raw_fd()));
+        }
+    }
+}
+
+/// Future of `AsyncFd::readable` and `AsyncFd::writable`.
+pub struct Readiness<'o, T: AsRawFd> {
+    fd: &'o AsyncFd<T>,
+    dir: Direction,
+    waiter: Option<WaiterId>,
+}
+
+impl<'o, T: AsRawFd> Future for Readiness<'o, T> {
+    type Output = IoResult<AsyncFdReadyGuard<'o, T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let token = future.fd.registration.token();
+        let event = ready!(Reactor::poll_ready(
+            cx,
+            token,
+            future.dir,
+            Wake::All,
+            &mut future.waiter
+        ))?;
+
+        Poll::Ready(Ok(AsyncFdReadyGuard {
+            fd: future.fd,
+            event: Some(event),
+        }))
+    }
+}
+
+impl<T: AsRawFd> Drop for Readiness<'_, T> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            let token = self.fd.registration.token();
+            Reactor::remove_waiter(token, self.dir, Wake::All, waiter);
+        }
+    }
+}
+
+/// Readiness of an `AsyncFd`, as returned by `AsyncFd::readable` and `AsyncFd::writable`.
+///
+/// Dropping the guard keeps the readiness, so the next wait returns right away.
+/// Call `clear_ready` once the fd returned `WouldBlock`, or use `try_io` which does it.
+pub struct AsyncFdReadyGuard<'o, T: AsRawFd> {
+    fd: &'o AsyncFd<T>,
+    event: Option<ReadyEvent>,
+}
+
+impl<'o, T: AsRawFd> AsyncFdReadyGuard<'o, T> {
+    /// Obtains the `AsyncFd` the guard is for.
+    pub fn get_ref(&self) -> &'o AsyncFd<T> {
+        self.fd
+    }
+
+    /// Obtains the readiness that was found.
+    ///
+    /// Empty once `clear_ready` was called.
+    pub fn ready(&self) -> Ready {
+        self.event.map_or(Ready::EMPTY, |event| event.ready())
+    }
+
+    /// Clears the readiness, the next wait only returns once the reactor reports it again.
+    ///
+    /// Readiness that arrived after this guard was handed out is kept.
+    pub fn clear_ready(&mut self) {
+        if let Some(event) = self.event.take() {
+            Reactor::clear_readiness(self.fd.registration.token(), event);
+        }
+    }
+
+    /// Runs `f` on the `AsyncFd`, clearing the readiness if it returns `WouldBlock`.
+    ///
+    /// The `WouldBlock` error is still handed back, callers wait again on it.
+    pub fn try_io<R>(&mut self, f: impl FnOnce(&'o AsyncFd<T>) -> IoResult<R>) -> IoResult<R> {
+        let result = f(self.fd);
+        if matches!(&result, Err(e) if e.kind() == ErrorKind::WouldBlock) {
+            self.clear_ready();
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::AsyncFd;
+    use crate::runtime::{Flavor, Runtime};
+    use mio::Interest;
+    use std::fs::File;
+    use std::future::{poll_fn, Future};
+    use std::io::{ErrorKind, Read, Write};
+    use std::os::unix::io::FromRawFd;
+    use std::pin::pin;
+    use std::task::Poll;
+    use std::time::Duration;
+
+    /// Non-blocking pipe, as its reading and writing ends.
+    fn pipe() -> (File, File) {
+        let mut fds = [0; 2];
+        // Safety: `fds` has room for both descriptors.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        for fd in fds {
+            // Safety: `fd` was just opened by us.
+            assert_eq!(
+                unsafe { libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) },
+                0
+            );
+        }
+        // Safety: both descriptors are open and nothing else owns them.
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+    }
+
+    #[test]
+    fn readable_pipe() {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        let future = async {
+            let (reader, mut writer) = pipe();
+            let reader = AsyncFd::with_interest(reader, Interest::READABLE).unwrap();
+            writer.write_all(b"abc").unwrap();
+
+            let mut guard = reader.readable().await.unwrap();
+            assert!(guard.ready().is_readable());
+
+            let mut buf = [0u8; 8];
+            let read = guard.try_io(|fd| fd.get_ref().read(&mut buf)).unwrap();
+            assert_eq!(&buf[..read], b"abc");
+
+            // Drained, so `try_io` clears the readiness.
+            let e = guard.try_io(|fd| fd.get_ref().read(&mut buf)).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::WouldBlock);
+            assert!(guard.ready().is_empty());
+
+            let mut readable = pin!(reader.readable());
+            poll_fn(|cx| {
+                assert!(readable.as_mut().poll(cx).is_pending());
+                Poll::Ready(())
+            })
+            .await;
+
+            writer.write_all(b"d").unwrap();
+            let mut guard = readable.await.unwrap();
+            let read = guard.try_io(|fd| fd.get_ref().read(&mut buf)).unwrap();
+            assert_eq!(&buf[..read], b"d");
+        };
+        runtime
+            .block_on_local_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    #[test]
+    fn clear_ready_waits_for_new_readiness() {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        let future = async {
+            let (reader, mut writer) = pipe();
+            let reader = AsyncFd::with_interest(reader, Interest::READABLE).unwrap();
+            writer.write_all(b"a").unwrap();
+
+            // Dropping the guard keeps the readiness.
+            reader.readable().await.unwrap();
+            let mut guard = reader.readable().await.unwrap();
+            guard.clear_ready();
+            assert!(guard.ready().is_empty());
+
+            let mut readable = pin!(reader.readable());
+            poll_fn(|cx| {
+                assert!(readable.as_mut().poll(cx).is_pending());
+                Poll::Ready(())
+            })
+            .await;
+
+            writer.write_all(b"b").unwrap();
+            assert!(readable.await.unwrap().ready().is_readable());
+        };
+        runtime
+            .block_on_local_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+}
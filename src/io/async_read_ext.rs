@@ -0,0 +1,413 @@
+use crate::io::{AsyncRead, OwnedWaiters};
+use std::future::Future;
+use std::io::{Error, ErrorKind, Result};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Future-returning methods for every `AsyncRead`.
+///
+/// Only `async_read` and `read_to_end` are cancel safe, the others lose
+/// whatever they read so far if the future is dropped before it completes.
+pub trait AsyncReadExt: AsyncRead {
+    /// Reads into `buf`, returning the amount of bytes read, 0 at the end of the stream.
+    ///
+    /// Cancel safe: nothing is read unless the future completes.
+    fn async_read<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadFuture {
+            reader: self,
+            buf,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Reads until `buf` is full, returning its length.
+    ///
+    /// Fails with `UnexpectedEof` if the stream ends first.
+    /// Not cancel safe, the bytes read so far are in `buf` but there is no telling how many.
+    fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadExactFuture {
+            reader: self,
+            buf,
+            filled: 0,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Reads until the end of the stream, appending to `buf`.
+    /// Returns the amount of bytes read.
+    ///
+    /// Cancel safe: the bytes read so far are in `buf` already.
+    fn read_to_end<'a>(&'a mut self, buf: &'a mut Vec<u8>) -> ReadToEndFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        let start = buf.len();
+        ReadToEndFuture {
+            reader: self,
+            buf,
+            start,
+            initialized: 0,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Reads until the end of the stream, appending to `buf`.
+    /// Returns the amount of bytes read.
+    ///
+    /// Fails with `InvalidData`, leaving `buf` as it was, if the bytes are not UTF-8.
+    /// Not cancel safe, the bytes read so far are lost.
+    fn read_to_string<'a>(&'a mut self, buf: &'a mut String) -> ReadToStringFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadToStringFuture {
+            reader: self,
+            buf,
+            bytes: Vec::new(),
+            initialized: 0,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Reads a big-endian `u16`.
+    ///
+    /// Not cancel safe, same as all the `read_u*` methods: the bytes read so far are lost.
+    fn read_u16(&mut self) -> ReadIntFuture<'_, Self, u16, 2>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u16::from_be_bytes)
+    }
+
+    /// Reads a little-endian `u16`.
+    fn read_u16_le(&mut self) -> ReadIntFuture<'_, Self, u16, 2>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u16::from_le_bytes)
+    }
+
+    /// Reads a big-endian `u32`.
+    fn read_u32(&mut self) -> ReadIntFuture<'_, Self, u32, 4>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u32::from_be_bytes)
+    }
+
+    /// Reads a little-endian `u32`.
+    fn read_u32_le(&mut self) -> ReadIntFuture<'_, Self, u32, 4>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u32::from_le_bytes)
+    }
+
+    /// Reads a big-endian `u64`.
+    fn read_u64(&mut self) -> ReadIntFuture<'_, Self, u64, 8>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u64::from_be_bytes)
+    }
+
+    /// Reads a little-endian `u64`.
+    fn read_u64_le(&mut self) -> ReadIntFuture<'_, Self, u64, 8>
+    where
+        Self: Unpin,
+    {
+        ReadIntFuture::new(self, u64::from_le_bytes)
+    }
+}
+
+impl<R: AsyncRead + ?Sized> AsyncReadExt for R {}
+
+fn eof() -> Error {
+    Error::new(ErrorKind::UnexpectedEof, "stream ended early")
+}
+
+/// Fills `buf` from `filled` on, advancing `filled`.
+fn poll_fill<R: AsyncRead + Unpin + ?Sized>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    buf: &mut [u8],
+    filled: &mut usize,
+) -> Poll<Result<()>> {
+    while *filled < buf.len() {
+        match ready!(Pin::new(&mut *reader).poll_read(cx, &mut buf[*filled..]))? {
+            0 => return Poll::Ready(Err(eof())),
+            size => *filled += size,
+        }
+    }
+    Poll::Ready(Ok(()))
+}
+
+/// Appends everything until the end of the stream to `buf`.
+///
+/// The tail `buf` is read into is only zeroed once, `initialized` keeps
+/// how many bytes past the length of `buf` are left from earlier reads.
+fn poll_to_end<R: AsyncRead + Unpin + ?Sized>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    buf: &mut Vec<u8>,
+    initialized: &mut usize,
+) -> Poll<Result<()>> {
+    const CHUNK: usize = 4096;
+
+    loop {
+        let len = buf.len();
+        if *initialized == 0 {
+            buf.resize(len + CHUNK, 0);
+            *initialized = CHUNK;
+        } else {
+            // Safety: these bytes were initialized by an earlier `resize` or read, and only
+            // `truncate` shrank `buf` since, the caller holds it for the whole read.
+            unsafe { buf.set_len(len + *initialized) };
+        }
+
+        let read = Pin::new(&mut *reader).poll_read(cx, &mut buf[len..]);
+
+        // Only the bytes actually read stay.
+        let size = match read {
+            Poll::Ready(Ok(size)) => size.min(*initialized),
+            _ => 0,
+        };
+        buf.truncate(len + size);
+        *initialized -= size;
+
+        match read {
+            Poll::Ready(Ok(0)) => return Poll::Ready(Ok(())),
+            Poll::Ready(Ok(_)) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+    }
+}
+
+/// Future of `AsyncReadExt::async_read`.
+pub struct ReadFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut [u8],
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> Future for ReadFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| Pin::new(&mut *future.reader).poll_read(cx, future.buf))
+    }
+}
+
+/// Future of `AsyncReadExt::read_exact`.
+pub struct ReadExactFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut [u8],
+    filled: usize,
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> Future for ReadExactFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        ready!(future.waiters.scope(|| poll_fill(
+            future.reader,
+            cx,
+            future.buf,
+            &mut future.filled
+        )))?;
+        Poll::Ready(Ok(future.buf.len()))
+    }
+}
+
+/// Future of `AsyncReadExt::read_to_end`.
+pub struct ReadToEndFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut Vec<u8>,
+    /// Length of `buf` before reading.
+    start: usize,
+    /// Bytes past the length of `buf` that are initialized, see `poll_to_end`.
+    initialized: usize,
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> Future for ReadToEndFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        ready!(future.waiters.scope(|| poll_to_end(
+            future.reader,
+            cx,
+            future.buf,
+            &mut future.initialized
+        )))?;
+        Poll::Ready(Ok(future.buf.len() - future.start))
+    }
+}
+
+/// Future of `AsyncReadExt::read_to_string`.
+pub struct ReadToStringFuture<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut String,
+    /// Bytes read so far, only appended to `buf` once they are all there and valid.
+    bytes: Vec<u8>,
+    /// Bytes past the length of `bytes` that are initialized, see `poll_to_end`.
+    initialized: usize,
+    waiters: OwnedWaiters,
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> Future for ReadToStringFuture<'_, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        ready!(future.waiters.scope(|| poll_to_end(
+            future.reader,
+            cx,
+            &mut future.bytes,
+            &mut future.initialized
+        )))?;
+
+        let bytes = std::mem::take(&mut future.bytes);
+        match String::from_utf8(bytes) {
+            Ok(string) => {
+                future.buf.push_str(&string);
+                Poll::Ready(Ok(string.len()))
+            }
+            Err(e) => Poll::Ready(Err(Error::new(ErrorKind::InvalidData, e))),
+        }
+    }
+}
+
+/// Future of the `AsyncReadExt::read_u*` methods, reading `N` bytes and converting them.
+pub struct ReadIntFuture<'a, R: ?Sized, T, const N: usize> {
+    reader: &'a mut R,
+    buf: [u8; N],
+    filled: usize,
+    convert: fn([u8; N]) -> T,
+    waiters: OwnedWaiters,
+}
+
+impl<'a, R: ?Sized, T, const N: usize> ReadIntFuture<'a, R, T, N> {
+    fn new(reader: &'a mut R, convert: fn([u8; N]) -> T) -> ReadIntFuture<'a, R, T, N> {
+        ReadIntFuture {
+            reader,
+            buf: [0; N],
+            filled: 0,
+            convert,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin + ?Sized, T, const N: usize> Future for ReadIntFuture<'_, R, T, N> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        ready!(future.waiters.scope(|| poll_fill(
+            future.reader,
+            cx,
+            &mut future.buf,
+            &mut future.filled
+        )))?;
+        Poll::Ready(Ok((future.convert)(future.buf)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::mock::MockReader;
+    use crate::io::AsyncReadExt;
+    use futures::executor::block_on;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn read_exact_across_reads() {
+        let mut reader = MockReader::new(&[b"he", b"llo", b" world"]);
+        let mut buf = [0u8; 5];
+        assert_eq!(block_on(reader.read_exact(&mut buf)).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+
+        let mut rest = Vec::new();
+        block_on(reader.read_to_end(&mut rest)).unwrap();
+        assert_eq!(rest, b" world");
+    }
+
+    #[test]
+    fn read_exact_fails_at_the_end() {
+        let mut reader = MockReader::new(&[b"abc"]);
+        let mut buf = [0u8; 4];
+        let err = block_on(reader.read_exact(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_to_end_appends() {
+        let big = vec![7u8; 10_000];
+        let mut reader = MockReader::new(&[b"ab", &big, b"c"]);
+        let mut buf = b"x".to_vec();
+
+        assert_eq!(block_on(reader.read_to_end(&mut buf)).unwrap(), 10_003);
+        assert_eq!(buf.len(), 10_004);
+        assert_eq!(&buf[..3], b"xab");
+        assert!(buf[3..10_003].iter().all(|b| *b == 7));
+        assert_eq!(buf[10_003], b'c');
+    }
+
+    #[test]
+    fn read_to_end_of_nothing() {
+        let mut reader = MockReader::new(&[]);
+        let mut buf = Vec::new();
+        assert_eq!(block_on(reader.read_to_end(&mut buf)).unwrap(), 0);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn read_to_string_checks_utf8() {
+        let mut reader = MockReader::new(&["hé".as_bytes(), "llo".as_bytes()]);
+        let mut buf = String::from(">");
+        assert_eq!(block_on(reader.read_to_string(&mut buf)).unwrap(), 6);
+        assert_eq!(buf, ">héllo");
+
+        let mut reader = MockReader::new(&[b"ok", &[0xff]]);
+        let mut buf = String::from(">");
+        let err = block_on(reader.read_to_string(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(buf, ">");
+    }
+
+    #[test]
+    fn read_ints_across_reads() {
+        let mut reader = MockReader::new(&[
+            &[0x12],
+            &[0x34, 0x34, 0x12],
+            &[0, 0, 0],
+            &[1, 1, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0, 2],
+            &[2, 0, 0, 0, 0, 0, 0],
+            &[0],
+        ]);
+        assert_eq!(block_on(reader.read_u16()).unwrap(), 0x1234);
+        assert_eq!(block_on(reader.read_u16_le()).unwrap(), 0x1234);
+        assert_eq!(block_on(reader.read_u32()).unwrap(), 1);
+        assert_eq!(block_on(reader.read_u32_le()).unwrap(), 1);
+        assert_eq!(block_on(reader.read_u64()).unwrap(), 2);
+        assert_eq!(block_on(reader.read_u64_le()).unwrap(), 2);
+
+        let err = block_on(reader.read_u16()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
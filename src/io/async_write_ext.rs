@@ -0,0 +1,306 @@
+use crate::io::{AsyncWrite, OwnedWaiters};
+use std::future::Future;
+use std::io::{Error, ErrorKind, IoSlice, Result};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Future-returning methods for every `AsyncWrite`.
+///
+/// Only `async_write` and `async_write_vectored` are cancel safe, dropping the
+/// other futures before they complete leaves it unknown how much was written.
+pub trait AsyncWriteExt: AsyncWrite {
+    /// Writes `buf`, returning the amount of bytes written.
+    ///
+    /// Cancel safe: nothing is written unless the future completes.
+    fn async_write<'a>(&'a mut self, buf: &'a [u8]) -> WriteFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        WriteFuture {
+            writer: self,
+            buf,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Writes from several buffers at once, see `AsyncWrite::poll_write_vectored`.
+    fn async_write_vectored<'a>(
+        &'a mut self,
+        bufs: &'a [IoSlice<'a>],
+    ) -> WriteVectoredFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        WriteVectoredFuture {
+            writer: self,
+            bufs,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Writes the whole of `buf`.
+    ///
+    /// Fails with `WriteZero` if the writer stops taking bytes.
+    fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> WriteAllFuture<'a, Self>
+    where
+        Self: Unpin,
+    {
+        WriteAllFuture {
+            writer: self,
+            buf,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Writes a big-endian `u16`.
+    fn write_u16(&mut self, n: u16) -> WriteIntFuture<'_, Self, 2>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_be_bytes())
+    }
+
+    /// Writes a little-endian `u16`.
+    fn write_u16_le(&mut self, n: u16) -> WriteIntFuture<'_, Self, 2>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_le_bytes())
+    }
+
+    /// Writes a big-endian `u32`.
+    fn write_u32(&mut self, n: u32) -> WriteIntFuture<'_, Self, 4>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_be_bytes())
+    }
+
+    /// Writes a little-endian `u32`.
+    fn write_u32_le(&mut self, n: u32) -> WriteIntFuture<'_, Self, 4>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_le_bytes())
+    }
+
+    /// Writes a big-endian `u64`.
+    fn write_u64(&mut self, n: u64) -> WriteIntFuture<'_, Self, 8>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_be_bytes())
+    }
+
+    /// Writes a little-endian `u64`.
+    fn write_u64_le(&mut self, n: u64) -> WriteIntFuture<'_, Self, 8>
+    where
+        Self: Unpin,
+    {
+        WriteIntFuture::new(self, n.to_le_bytes())
+    }
+
+    /// Writes out everything buffered so far.
+    fn flush(&mut self) -> FlushFuture<'_, Self>
+    where
+        Self: Unpin,
+    {
+        FlushFuture {
+            writer: self,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+
+    /// Flushes and closes the writing side, see `AsyncWrite::poll_shutdown`.
+    fn shutdown(&mut self) -> ShutdownFuture<'_, Self>
+    where
+        Self: Unpin,
+    {
+        ShutdownFuture {
+            writer: self,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+}
+
+impl<W: AsyncWrite + ?Sized> AsyncWriteExt for W {}
+
+/// Writes `buf` out, advancing it past what was written.
+fn poll_write_all<W: AsyncWrite + Unpin + ?Sized>(
+    writer: &mut W,
+    cx: &mut Context<'_>,
+    buf: &mut &[u8],
+) -> Poll<Result<()>> {
+    while !buf.is_empty() {
+        match ready!(Pin::new(&mut *writer).poll_write(cx, buf))? {
+            0 => {
+                return Poll::Ready(Err(Error::new(
+                    ErrorKind::WriteZero,
+                    "writer stopped taking bytes",
+                )))
+            }
+            size => *buf = &buf[size..],
+        }
+    }
+    Poll::Ready(Ok(()))
+}
+
+/// Future of `AsyncWriteExt::async_write`.
+pub struct WriteFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    buf: &'a [u8],
+    waiters: OwnedWaiters,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for WriteFuture<'_, W> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| Pin::new(&mut *future.writer).poll_write(cx, future.buf))
+    }
+}
+
+/// Future of `AsyncWriteExt::async_write_vectored`.
+pub struct WriteVectoredFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    bufs: &'a [IoSlice<'a>],
+    waiters: OwnedWaiters,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for WriteVectoredFuture<'_, W> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| Pin::new(&mut *future.writer).poll_write_vectored(cx, future.bufs))
+    }
+}
+
+/// Future of `AsyncWriteExt::write_all`.
+pub struct WriteAllFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    /// What is left to write.
+    buf: &'a [u8],
+    waiters: OwnedWaiters,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for WriteAllFuture<'_, W> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| poll_write_all(future.writer, cx, &mut future.buf))
+    }
+}
+
+/// Future of the `AsyncWriteExt::write_u*` methods, writing `N` bytes.
+pub struct WriteIntFuture<'a, W: ?Sized, const N: usize> {
+    writer: &'a mut W,
+    buf: [u8; N],
+    written: usize,
+    waiters: OwnedWaiters,
+}
+
+impl<'a, W: ?Sized, const N: usize> WriteIntFuture<'a, W, N> {
+    fn new(writer: &'a mut W, buf: [u8; N]) -> WriteIntFuture<'a, W, N> {
+        WriteIntFuture {
+            writer,
+            buf,
+            written: 0,
+            waiters: OwnedWaiters::default(),
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized, const N: usize> Future for WriteIntFuture<'_, W, N> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let mut left = &future.buf[future.written..];
+        let result = future
+            .waiters
+            .scope(|| poll_write_all(future.writer, cx, &mut left));
+        future.written = N - left.len();
+        result
+    }
+}
+
+/// Future of `AsyncWriteExt::flush`.
+pub struct FlushFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    waiters: OwnedWaiters,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for FlushFuture<'_, W> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| Pin::new(&mut *future.writer).poll_flush(cx))
+    }
+}
+
+/// Future of `AsyncWriteExt::shutdown`.
+pub struct ShutdownFuture<'a, W: ?Sized> {
+    writer: &'a mut W,
+    waiters: OwnedWaiters,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for ShutdownFuture<'_, W> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future
+            .waiters
+            .scope(|| Pin::new(&mut *future.writer).poll_shutdown(cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::mock::MockWriter;
+    use crate::io::AsyncWriteExt;
+    use futures::executor::block_on;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn write_all_across_short_writes() {
+        let mut writer = MockWriter::new(3);
+        block_on(writer.write_all(b"hello world")).unwrap();
+        assert_eq!(writer.written, b"hello world");
+        assert_eq!(writer.writes, 4);
+    }
+
+    #[test]
+    fn write_all_fails_once_nothing_is_taken() {
+        let mut writer = MockWriter::new(0);
+        let err = block_on(writer.write_all(b"hello")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn write_ints() {
+        let mut writer = MockWriter::new(3);
+        block_on(writer.write_u16(0x1234)).unwrap();
+        block_on(writer.write_u16_le(0x1234)).unwrap();
+        block_on(writer.write_u32(1)).unwrap();
+        block_on(writer.write_u32_le(1)).unwrap();
+        block_on(writer.write_u64(2)).unwrap();
+        block_on(writer.write_u64_le(2)).unwrap();
+
+        let mut expected = vec![0x12, 0x34, 0x34, 0x12, 0, 0, 0, 1, 1, 0, 0, 0];
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(writer.written, expected);
+    }
+}
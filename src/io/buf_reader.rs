@@ -0,0 +1,154 @@
+use crate::io::{AsyncBufRead, AsyncRead};
+use std::io::Result;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Default capacity of `BufReader` and `BufWriter`.
+pub(crate) const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Adds buffering to an `AsyncRead`, so small reads don't cost a syscall each.
+///
+/// Reads at least as large as the buffer skip it when it is empty.
+pub struct BufReader<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    /// Bytes of `buf` before `pos` were consumed already.
+    pos: usize,
+    /// Bytes of `buf` from `filled` on are not read yet.
+    filled: usize,
+}
+
+impl<R: AsyncRead> BufReader<R> {
+    /// Wraps `inner` with a buffer of the default capacity, 8 KiB.
+    pub fn new(inner: R) -> BufReader<R> {
+        BufReader::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    /// Wraps `inner` with a buffer of `capacity` bytes.
+    pub fn with_capacity(capacity: usize, inner: R) -> BufReader<R> {
+        BufReader {
+            inner,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl<R> BufReader<R> {
+    /// Obtains a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Obtains a mutable reference to the wrapped reader.
+    ///
+    /// Reading from it directly skips what is buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Hands the wrapped reader back, whatever is buffered is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Obtains the bytes buffered but not read yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for BufReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let reader = self.get_mut();
+
+        if reader.pos == reader.filled && buf.len() >= reader.buf.len() {
+            return Pin::new(&mut reader.inner).poll_read(cx, buf);
+        }
+
+        let available = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+        let size = available.len().min(buf.len());
+        buf[..size].copy_from_slice(&available[..size]);
+        Pin::new(reader).consume(size);
+        Poll::Ready(Ok(size))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for BufReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        let reader = self.get_mut();
+
+        if reader.pos == reader.filled {
+            let size = ready!(Pin::new(&mut reader.inner).poll_read(cx, &mut reader.buf))?;
+            reader.pos = 0;
+            reader.filled = size;
+        }
+        Poll::Ready(Ok(&reader.buf[reader.pos..reader.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let reader = self.get_mut();
+        reader.pos = (reader.pos + amt).min(reader.filled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufReader;
+    use crate::io::mock::MockReader;
+    use crate::io::{AsyncBufReadExt, AsyncReadExt};
+    use futures::executor::block_on;
+
+    #[test]
+    fn fill_buf_keeps_bytes_until_consumed() {
+        let mut reader = BufReader::with_capacity(4, MockReader::new(&[b"abcdef"]));
+
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"abcd");
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"abcd");
+        reader.consume(3);
+        assert_eq!(reader.buffer(), b"d");
+
+        // Whatever is left is returned before reading again.
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"d");
+        reader.consume(1);
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"ef");
+        reader.consume(2);
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"");
+    }
+
+    #[test]
+    fn consume_stops_at_what_is_buffered() {
+        let mut reader = BufReader::new(MockReader::new(&[b"ab", b"cd"]));
+        block_on(reader.fill_buf()).unwrap();
+        reader.consume(10);
+        assert!(reader.buffer().is_empty());
+        assert_eq!(block_on(reader.fill_buf()).unwrap(), b"cd");
+    }
+
+    #[test]
+    fn small_reads_go_through_the_buffer() {
+        let mut reader = BufReader::with_capacity(8, MockReader::new(&[b"hello world"]));
+        let mut buf = [0u8; 2];
+        block_on(reader.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"he");
+        assert_eq!(reader.buffer(), b"llo wo");
+
+        let mut rest = Vec::new();
+        block_on(reader.read_to_end(&mut rest)).unwrap();
+        assert_eq!(rest, b"llo world");
+    }
+
+    #[test]
+    fn large_reads_skip_the_empty_buffer() {
+        let mut reader = BufReader::with_capacity(4, MockReader::new(&[b"hello world"]));
+        let mut buf = [0u8; 8];
+        assert_eq!(block_on(reader.async_read(&mut buf)).unwrap(), 8);
+        assert_eq!(&buf, b"hello wo");
+        assert!(reader.buffer().is_empty());
+    }
+}
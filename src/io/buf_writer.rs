@@ -0,0 +1,164 @@
+use crate::io::buf_reader::DEFAULT_CAPACITY;
+use crate::io::AsyncWrite;
+use std::io::{Error, ErrorKind, Result};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Adds buffering to an `AsyncWrite`, so small writes don't cost a syscall each.
+///
+/// Buffered bytes only go out once the buffer is full, or on `poll_flush` and `poll_shutdown`.
+/// Dropping a `BufWriter` without flushing it loses them.
+pub struct BufWriter<W> {
+    inner: W,
+    buf: Vec<u8>,
+    /// Bytes of `buf` before `written` went out already.
+    written: usize,
+}
+
+impl<W: AsyncWrite> BufWriter<W> {
+    /// Wraps `inner` with a buffer of the default capacity, 8 KiB.
+    pub fn new(inner: W) -> BufWriter<W> {
+        BufWriter::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    /// Wraps `inner` with a buffer of `capacity` bytes.
+    pub fn with_capacity(capacity: usize, inner: W) -> BufWriter<W> {
+        BufWriter {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            written: 0,
+        }
+    }
+}
+
+impl<W> BufWriter<W> {
+    /// Obtains a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Obtains a mutable reference to the wrapped writer.
+    ///
+    /// Writing to it directly gets ahead of what is buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Hands the wrapped writer back, whatever is buffered is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Obtains the bytes buffered but not written yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.written..]
+    }
+}
+
+impl<W: AsyncWrite + Unpin> BufWriter<W> {
+    /// Writes out the buffer to the wrapped writer, without flushing it.
+    fn poll_flush_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while self.written < self.buf.len() {
+            let buf = &self.buf[self.written..];
+            match ready!(Pin::new(&mut self.inner).poll_write(cx, buf))? {
+                0 => {
+                    return Poll::Ready(Err(Error::new(
+                        ErrorKind::WriteZero,
+                        "writer stopped taking bytes",
+                    )))
+                }
+                size => self.written += size,
+            }
+        }
+
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for BufWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        let writer = self.get_mut();
+
+        if writer.buf.len() + buf.len() > writer.buf.capacity() {
+            ready!(writer.poll_flush_buf(cx))?;
+        }
+
+        if buf.len() >= writer.buf.capacity() {
+            Pin::new(&mut writer.inner).poll_write(cx, buf)
+        } else {
+            writer.buf.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let writer = self.get_mut();
+        ready!(writer.poll_flush_buf(cx))?;
+        Pin::new(&mut writer.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let writer = self.get_mut();
+        ready!(writer.poll_flush_buf(cx))?;
+        Pin::new(&mut writer.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufWriter;
+    use crate::io::mock::MockWriter;
+    use crate::io::AsyncWriteExt;
+    use futures::executor::block_on;
+
+    #[test]
+    fn small_writes_wait_for_a_full_buffer() {
+        let mut writer = BufWriter::with_capacity(8, MockWriter::new(usize::MAX));
+        block_on(writer.write_all(b"abc")).unwrap();
+        block_on(writer.write_all(b"def")).unwrap();
+        assert!(writer.get_ref().written.is_empty());
+        assert_eq!(writer.buffer(), b"abcdef");
+
+        // Does not fit anymore, what is buffered goes out first.
+        block_on(writer.write_all(b"ghi")).unwrap();
+        assert_eq!(writer.get_ref().written, b"abcdef");
+        assert_eq!(writer.buffer(), b"ghi");
+        assert_eq!(writer.get_ref().flushes, 0);
+    }
+
+    #[test]
+    fn flush_buffer_across_short_writes() {
+        let mut writer = BufWriter::with_capacity(8, MockWriter::new(2));
+        block_on(writer.write_all(b"hello")).unwrap();
+        block_on(writer.flush()).unwrap();
+
+        assert_eq!(writer.get_ref().written, b"hello");
+        assert_eq!(writer.get_ref().writes, 3);
+        assert_eq!(writer.get_ref().flushes, 1);
+        assert!(writer.buffer().is_empty());
+    }
+
+    #[test]
+    fn large_writes_skip_the_buffer() {
+        let mut writer = BufWriter::with_capacity(4, MockWriter::new(usize::MAX));
+        block_on(writer.write_all(b"ab")).unwrap();
+        block_on(writer.write_all(b"cdefgh")).unwrap();
+
+        assert_eq!(writer.get_ref().written, b"abcdefgh");
+        assert_eq!(writer.get_ref().writes, 2);
+        assert!(writer.buffer().is_empty());
+    }
+
+    #[test]
+    fn shutdown_writes_out_the_buffer_first() {
+        let mut writer = BufWriter::new(MockWriter::new(usize::MAX));
+        block_on(writer.write_all(b"bye")).unwrap();
+        block_on(writer.shutdown()).unwrap();
+
+        let inner = writer.into_inner();
+        assert_eq!(inner.written, b"bye");
+        assert!(inner.shut_down);
+    }
+}
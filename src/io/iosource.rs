@@ -1,52 +1,539 @@
-use mio::event::Event;
-use mio::Token;
+use crate::io::reactor::Direction;
+use crate::io::{Ready, ReadyEvent};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::task::Waker;
 
-/// Represents a connection between a waker and the reactor
+// Layout of `IoSource::readiness`:
+// the lower 16 bits hold the `Ready` flags, the 8 bits above them the tick.
+const READY_MASK: usize = 0xffff;
+const TICK_SHIFT: usize = 16;
+const TICK_MASK: usize = 0xff << TICK_SHIFT;
+
+/// How a waiter wants to be woken once its direction becomes ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    /// Woken on every readiness event, along with every other `Wake::All` waiter.
+    All,
+
+    /// Only one `Wake::One` waiter is woken per readiness event.
+    /// Once it sees the readiness, it passes the wakeup on to the next one,
+    /// so readiness it left behind is never stranded.
+    One,
+}
+
+/// Identifies a waiter registered in an `IoSource`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaiterId(u64);
+
+/// Id of the waiters added by `Waiters::add_task_waiter`, `next_id` never gets there.
+const TASK_WAITER: WaiterId = WaiterId(u64::MAX);
+
+/// Task waiting for readiness in one direction.
+struct Waiter {
+    id: WaiterId,
+    waker: Waker,
+    wake: Wake,
+}
+
+/// Represents a connection between wakers and the reactor
+///
+/// Shared between the reactor's source table and whoever is using it,
+/// each source has its own lock, so sources never wait on each other.
 pub struct IoSource {
-    read_waker: Option<Waker>,
-    write_waker: Option<Waker>,
-    token: Token,
+    waiters: Mutex<Waiters>,
+
+    /// Readiness set by the reactor thread, cleared by the I/O futures.
+    readiness: AtomicUsize,
+
+    /// Generation of the token the source was registered with.
+    generation: usize,
+}
+
+/// Waiters of an `IoSource`, for every direction.
+pub struct Waiters {
+    read_waiters: Vec<Waiter>,
+    write_waiters: Vec<Waiter>,
+    priority_waiters: Vec<Waiter>,
+
+    /// Id given to the next waiter.
+    next_id: u64,
 }
 
 impl IoSource {
-    pub fn new(token: usize) -> IoSource {
+    pub fn new(generation: usize) -> IoSource {
         IoSource {
-            read_waker: None,
-            write_waker: None,
-            token: Token(token),
+            waiters: Mutex::new(Waiters {
+                read_waiters: Vec::new(),
+                write_waiters: Vec::new(),
+                priority_waiters: Vec::new(),
+                next_id: 0,
+            }),
+            readiness: AtomicUsize::new(0),
+            generation,
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Locks the waiters of the source.
+    ///
+    /// Readiness is checked and waiters are added under this lock, and the reactor
+    /// sets readiness under it too, so a wakeup can not slip in between them.
+    ///
+    /// A panic while holding the lock poisons it, the waiters are still fine to use.
+    pub fn waiters(&self) -> MutexGuard<'_, Waiters> {
+        match self.waiters.lock() {
+            Ok(waiters) => waiters,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Adds `ready`, the readiness of an event, and bumps the tick.
+    /// Only the reactor thread calls this, while holding the waiters lock.
+    pub fn set_readiness(&self, ready: Ready) {
+        let _ = self
+            .readiness
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                let tick = ((current & TICK_MASK) >> TICK_SHIFT).wrapping_add(1) & 0xff;
+                let bits = (current & READY_MASK) | ready.as_usize();
+                Some((tick << TICK_SHIFT) | bits)
+            });
+    }
+
+    /// Obtains the current readiness, along with its tick.
+    pub fn readiness(&self) -> ReadyEvent {
+        let current = self.readiness.load(Ordering::Acquire);
+        ReadyEvent {
+            ready: Ready::from_usize(current & READY_MASK),
+            tick: ((current & TICK_MASK) >> TICK_SHIFT) as u8,
+        }
+    }
+
+    /// Clears the readiness of `event`,
+    /// unless the reactor set new readiness after it was observed.
+    ///
+    /// Closed flags are never cleared, a closed half does not open up again.
+    pub fn clear_readiness(&self, event: ReadyEvent) {
+        let clear = event.ready - (Ready::READ_CLOSED | Ready::WRITE_CLOSED);
+        let _ = self
+            .readiness
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                let tick = ((current & TICK_MASK) >> TICK_SHIFT) as u8;
+                if tick != event.tick {
+                    return None;
+                }
+
+                let bits = Ready::from_usize(current & READY_MASK) - clear;
+                Some((current & TICK_MASK) | bits.as_usize())
+            });
+    }
+}
+
+impl Waiters {
+    pub fn is_empty(&self) -> bool {
+        self.read_waiters.is_empty()
+            && self.write_waiters.is_empty()
+            && self.priority_waiters.is_empty()
+    }
+
+    /// Takes the wakers of the waiters of every direction `ready` covers.
+    ///
+    /// The wakers are handed out instead of being woken here, so they are
+    /// woken and dropped after the waiters lock is released: dropping the
+    /// last waker of a task drops its futures, which take that lock again.
+    ///
+    /// Errors and hang-ups count for every direction they concern,
+    /// so tasks waiting on a closed source get to see it.
+    pub fn wake_ready(&mut self, ready: Ready, wakers: &mut Vec<Waker>) {
+        for dir in [Direction::Read, Direction::Write, Direction::Priority] {
+            if ready.intersects(dir.mask()) {
+                self.wake(dir, wakers);
+            }
+        }
+    }
+
+    /// Takes the wakers of every `Wake::All` waiter and the first `Wake::One` waiter of `dir`.
+    pub fn wake(&mut self, dir: Direction, wakers: &mut Vec<Waker>) {
+        let mut woke_one = false;
+        let mut i = 0;
+        let waiters = self.waiters_mut(dir);
+        while i < waiters.len() {
+            let wake = match waiters[i].wake {
+                Wake::All => true,
+                Wake::One => !std::mem::replace(&mut woke_one, true),
+            };
+
+            if wake {
+                wakers.push(waiters.remove(i).waker);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Takes the waker of the first `Wake::One` waiter of `dir`.
+    pub fn wake_next(&mut self, dir: Direction) -> Option<Waker> {
+        let waiters = self.waiters_mut(dir);
+        let pos = waiters.iter().position(|w| w.wake == Wake::One)?;
+        Some(waiters.remove(pos).waker)
+    }
+
+    /// Registers the waker of a waiter.
+    ///
+    /// `id` is the waiter's previous registration, which gets updated in place
+    /// if it is still there, and is set to the new registration otherwise.
+    pub fn add_waiter(
+        &mut self,
+        dir: Direction,
+        waker: &Waker,
+        wake: Wake,
+        id: &mut Option<WaiterId>,
+    ) {
+        if let Some(current) = *id {
+            if let Some(waiter) = self.waiters_mut(dir).iter_mut().find(|w| w.id == current) {
+                if !waiter.waker.will_wake(waker) {
+                    waiter.waker = waker.clone();
+                }
+                waiter.wake = wake;
+                return;
+            }
+        }
+
+        let new = WaiterId(self.next_id);
+        self.next_id += 1;
+
+        self.waiters_mut(dir).push(Waiter {
+            id: new,
+            waker: waker.clone(),
+            wake,
+        });
+        *id = Some(new);
+    }
+
+    /// Registers the waker of a task polling without a waiter of its own, once per task.
+    ///
+    /// It is a `Wake::All` waiter that stays until `dir` gets ready, the same task polling again
+    /// only updates it. Nobody holds its id to remove it earlier, so the crate's I/O futures
+    /// register owned waiters instead, see `OwnedWaiters`.
+    pub fn add_task_waiter(&mut self, dir: Direction, waker: &Waker) {
+        let waiters = self.waiters_mut(dir);
+        if waiters
+            .iter()
+            .any(|w| w.id == TASK_WAITER && w.waker.will_wake(waker))
+        {
+            return;
+        }
+
+        waiters.push(Waiter {
+            id: TASK_WAITER,
+            waker: waker.clone(),
+            wake: Wake::All,
+        });
+    }
+
+    /// Removes a waiter, if it was not woken already, handing out its waker.
+    ///
+    /// A `Wake::One` waiter that was already woken passes the wakeup on
+    /// if the source is still `ready`, since it will never consume it.
+    /// The waker to wake is pushed into `wakers` then.
+    pub fn remove_waiter(
+        &mut self,
+        dir: Direction,
+        wake: Wake,
+        id: WaiterId,
+        ready: Ready,
+        wakers: &mut Vec<Waker>,
+    ) -> Option<Waker> {
+        let waiters = self.waiters_mut(dir);
+        match waiters.iter().position(|w| w.id == id) {
+            Some(pos) => Some(waiters.remove(pos).waker),
+            None if wake == Wake::One && ready.intersects(dir.mask()) => {
+                wakers.extend(self.wake_next(dir));
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn waiters_mut(&mut self, dir: Direction) -> &mut Vec<Waiter> {
+        match dir {
+            Direction::Read => &mut self.read_waiters,
+            Direction::Write => &mut self.write_waiters,
+            Direction::Priority => &mut self.priority_waiters,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IoSource, WaiterId, Waiters, Wake};
+    use crate::io::reactor::Direction;
+    use crate::io::{Ready, ReadyEvent};
+    use futures::task::{self, ArcWake};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Waker;
+
+    /// Waker counting how many times it was woken.
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = task::waker(Arc::clone(&counter));
+        (counter, waker)
+    }
+
+    fn wakes(counter: &Counter) -> usize {
+        counter.0.load(Ordering::Relaxed)
+    }
+
+    fn add(waiters: &mut Waiters, dir: Direction, waker: &Waker, wake: Wake) -> WaiterId {
+        let mut id = None;
+        waiters.add_waiter(dir, waker, wake, &mut id);
+        id.expect("waiter was not registered")
+    }
 
-    pub fn has_wakers(&self) -> bool {
-        self.read_waker.is_some() || self.write_waker.is_some()
+    fn wake_all(wakers: &mut Vec<Waker>) {
+        wakers.drain(..).for_each(Waker::wake);
     }
 
-    pub fn wake_with_event(&self, ev: &Event) {
-        if let (true, Some(waker)) = (ev.is_readable(), &self.read_waker) {
-            waker.wake_by_ref();
+    #[test]
+    fn wake_all_wakes_every_waiter() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let readers: Vec<_> = (0..3).map(|_| counter()).collect();
+        let (writer, writer_waker) = counter();
+
+        for (_, waker) in &readers {
+            add(&mut waiters, Direction::Read, waker, Wake::All);
         }
+        add(&mut waiters, Direction::Write, &writer_waker, Wake::All);
 
-        if let (true, Some(waker)) = (ev.is_writable(), &self.write_waker) {
-            waker.wake_by_ref();
+        let mut wakers = Vec::new();
+        waiters.wake_ready(Ready::READABLE, &mut wakers);
+        assert_eq!(wakers.len(), 3);
+        wake_all(&mut wakers);
+
+        for (reader, _) in &readers {
+            assert_eq!(wakes(reader), 1);
         }
+        assert_eq!(wakes(&writer), 0);
+        assert!(!waiters.is_empty());
+    }
 
-        // todo: handle closing and stuff
+    #[test]
+    fn errors_wake_every_direction() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let counters: Vec<_> = [Direction::Read, Direction::Write, Direction::Priority]
+            .into_iter()
+            .map(|dir| {
+                let (counter, waker) = counter();
+                add(&mut waiters, dir, &waker, Wake::All);
+                counter
+            })
+            .collect();
+
+        let mut wakers = Vec::new();
+        waiters.wake_ready(Ready::ERROR, &mut wakers);
+        wake_all(&mut wakers);
+
+        assert!(counters.iter().all(|counter| wakes(counter) == 1));
+        assert!(waiters.is_empty());
     }
 
-    pub fn change_read_waker(&mut self, waker: &Waker) {
-        self.read_waker = Some(waker.clone())
+    #[test]
+    fn wake_one_wakes_a_single_waiter() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (first, first_waker) = counter();
+        let (second, second_waker) = counter();
+        let (all, all_waker) = counter();
+
+        add(&mut waiters, Direction::Read, &first_waker, Wake::One);
+        add(&mut waiters, Direction::Read, &second_waker, Wake::One);
+        add(&mut waiters, Direction::Read, &all_waker, Wake::All);
+
+        let mut wakers = Vec::new();
+        waiters.wake(Direction::Read, &mut wakers);
+        wake_all(&mut wakers);
+
+        assert_eq!(wakes(&first), 1);
+        assert_eq!(wakes(&second), 0);
+        assert_eq!(wakes(&all), 1);
+
+        // The next one is woken once the first saw the readiness.
+        let next = waiters.wake_next(Direction::Read).expect("no waiter left");
+        next.wake();
+        assert_eq!(wakes(&second), 1);
+        assert!(waiters.is_empty());
     }
 
-    pub fn change_write_waker(&mut self, waker: &Waker) {
-        self.write_waker = Some(waker.clone())
+    #[test]
+    fn wake_one_hands_off_when_dropped_after_its_wakeup() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (first, first_waker) = counter();
+        let (second, second_waker) = counter();
+
+        let first_id = add(&mut waiters, Direction::Read, &first_waker, Wake::One);
+        add(&mut waiters, Direction::Read, &second_waker, Wake::One);
+
+        let mut wakers = Vec::new();
+        waiters.wake(Direction::Read, &mut wakers);
+        wake_all(&mut wakers);
+        assert_eq!(wakes(&first), 1);
+
+        // Dropped before consuming the readiness, which goes to the next waiter.
+        let removed = waiters.remove_waiter(
+            Direction::Read,
+            Wake::One,
+            first_id,
+            Ready::READABLE,
+            &mut wakers,
+        );
+        assert!(removed.is_none());
+        wake_all(&mut wakers);
+        assert_eq!(wakes(&second), 1);
+        assert!(waiters.is_empty());
     }
 
-    pub fn get_read_waker(&self) -> &Option<Waker> {
-        &self.read_waker
+    #[test]
+    fn wake_one_does_not_hand_off_without_readiness() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (_, first_waker) = counter();
+        let (second, second_waker) = counter();
+
+        let first_id = add(&mut waiters, Direction::Read, &first_waker, Wake::One);
+        add(&mut waiters, Direction::Read, &second_waker, Wake::One);
+
+        let mut wakers = Vec::new();
+        waiters.wake(Direction::Read, &mut wakers);
+        wakers.clear();
+
+        // Readiness was consumed, nothing to pass on.
+        waiters.remove_waiter(
+            Direction::Read,
+            Wake::One,
+            first_id,
+            Ready::EMPTY,
+            &mut wakers,
+        );
+        assert!(wakers.is_empty());
+        assert_eq!(wakes(&second), 0);
+    }
+
+    #[test]
+    fn removed_waiter_is_not_woken() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (counter, waker) = counter();
+
+        let id = add(&mut waiters, Direction::Write, &waker, Wake::All);
+        let mut wakers = Vec::new();
+        let removed =
+            waiters.remove_waiter(Direction::Write, Wake::All, id, Ready::EMPTY, &mut wakers);
+        assert!(removed.is_some());
+        assert!(waiters.is_empty());
+
+        waiters.wake_ready(Ready::WRITABLE, &mut wakers);
+        assert!(wakers.is_empty());
+        assert_eq!(wakes(&counter), 0);
     }
 
-    pub fn get_write_waker(&self) -> &Option<Waker> {
-        &self.write_waker
+    #[test]
+    fn add_waiter_updates_in_place() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (old, old_waker) = counter();
+        let (new, new_waker) = counter();
+
+        let mut id = None;
+        waiters.add_waiter(Direction::Read, &old_waker, Wake::All, &mut id);
+        let first = id;
+        waiters.add_waiter(Direction::Read, &new_waker, Wake::All, &mut id);
+        assert_eq!(id, first);
+
+        let mut wakers = Vec::new();
+        waiters.wake(Direction::Read, &mut wakers);
+        assert_eq!(wakers.len(), 1);
+        wake_all(&mut wakers);
+
+        assert_eq!(wakes(&old), 0);
+        assert_eq!(wakes(&new), 1);
+    }
+
+    #[test]
+    fn task_waiters_are_added_once_per_task() {
+        let source = IoSource::new(0);
+        let mut waiters = source.waiters();
+        let (first, first_waker) = counter();
+        let (second, second_waker) = counter();
+
+        waiters.add_task_waiter(Direction::Read, &first_waker);
+        waiters.add_task_waiter(Direction::Read, &second_waker);
+        waiters.add_task_waiter(Direction::Read, &first_waker);
+
+        let mut wakers = Vec::new();
+        waiters.wake_ready(Ready::READABLE, &mut wakers);
+        assert_eq!(wakers.len(), 2);
+        wake_all(&mut wakers);
+        assert_eq!(wakes(&first), 1);
+        assert_eq!(wakes(&second), 1);
+        assert!(waiters.is_empty());
+    }
+
+    #[test]
+    fn clear_readiness() {
+        let source = IoSource::new(0);
+        source.set_readiness(Ready::READABLE | Ready::WRITABLE);
+
+        let event = source.readiness();
+        assert!(event.ready.is_readable() && event.ready.is_writable());
+
+        // Only the readable half of it was consumed.
+        source.clear_readiness(ReadyEvent {
+            ready: Ready::READABLE,
+            ..event
+        });
+        let ready = source.readiness().ready;
+        assert!(!ready.is_readable());
+        assert!(ready.is_writable());
+    }
+
+    #[test]
+    fn stale_clear_readiness_is_ignored() {
+        let source = IoSource::new(0);
+        source.set_readiness(Ready::READABLE);
+        let stale = source.readiness();
+
+        // New readiness came in after `stale` was observed, it must not get lost.
+        source.set_readiness(Ready::READABLE);
+        source.clear_readiness(stale);
+        assert!(source.readiness().ready.is_readable());
+
+        source.clear_readiness(source.readiness());
+        assert!(source.readiness().ready.is_empty());
+    }
+
+    #[test]
+    fn closed_readiness_is_never_cleared() {
+        let source = IoSource::new(0);
+        source.set_readiness(Ready::READABLE | Ready::READ_CLOSED);
+        source.clear_readiness(source.readiness());
+
+        let ready = source.readiness().ready;
+        assert!(!ready.is_readable());
+        assert!(ready.is_read_closed());
     }
 }
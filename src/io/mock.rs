@@ -0,0 +1,109 @@
+use crate::io::{AsyncRead, AsyncWrite};
+use std::collections::VecDeque;
+use std::io::Result;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// In-memory reader handing out its chunks one read at a time,
+/// returning `Poll::Pending` before each of them.
+pub(crate) struct MockReader {
+    chunks: VecDeque<Vec<u8>>,
+    pending: bool,
+    /// Amount of `poll_read` calls.
+    pub(crate) reads: usize,
+}
+
+impl MockReader {
+    pub(crate) fn new(chunks: &[&[u8]]) -> MockReader {
+        MockReader {
+            chunks: chunks.iter().map(|chunk| chunk.to_vec()).collect(),
+            pending: true,
+            reads: 0,
+        }
+    }
+}
+
+impl AsyncRead for MockReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let reader = self.get_mut();
+        reader.reads += 1;
+
+        let pending = reader.pending;
+        reader.pending = !pending;
+        if pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let chunk = match reader.chunks.front_mut() {
+            Some(chunk) => chunk,
+            None => return Poll::Ready(Ok(0)),
+        };
+
+        let size = chunk.len().min(buf.len());
+        buf[..size].copy_from_slice(&chunk[..size]);
+        chunk.drain(..size);
+        if chunk.is_empty() {
+            reader.chunks.pop_front();
+        }
+        Poll::Ready(Ok(size))
+    }
+}
+
+/// In-memory writer taking at most `max_write` bytes per write,
+/// returning `Poll::Pending` before each of them.
+pub(crate) struct MockWriter {
+    pub(crate) written: Vec<u8>,
+    max_write: usize,
+    pending: bool,
+    /// Amount of `poll_write` calls that wrote something.
+    pub(crate) writes: usize,
+    pub(crate) flushes: usize,
+    pub(crate) shut_down: bool,
+}
+
+impl MockWriter {
+    pub(crate) fn new(max_write: usize) -> MockWriter {
+        MockWriter {
+            written: Vec::new(),
+            max_write,
+            pending: true,
+            writes: 0,
+            flushes: 0,
+            shut_down: false,
+        }
+    }
+}
+
+impl AsyncWrite for MockWriter {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        let writer = self.get_mut();
+        assert!(!writer.shut_down, "wrote after shutdown");
+
+        let pending = writer.pending;
+        writer.pending = !pending;
+        if pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let size = buf.len().min(writer.max_write);
+        writer.written.extend_from_slice(&buf[..size]);
+        writer.writes += 1;
+        Poll::Ready(Ok(size))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().shut_down = true;
+        Poll::Ready(Ok(()))
+    }
+}
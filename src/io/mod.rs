@@ -1,22 +1,79 @@
-// Obligatory Future import.
-pub use std::future::Future;
-
 /// IoSource trait.
-pub mod iosource;
-pub use iosource::IoSource;
+mod iosource;
+pub(crate) use iosource::IoSource;
+pub use iosource::{WaiterId, Wake};
+
+/// Table of the sources registered in the reactor.
+mod sources;
+
+/// Readiness of I/O sources.
+mod ready;
+pub use ready::{Ready, ReadyEvent};
 
 /// I/O Reactor.
 pub mod reactor;
-pub use reactor::{Handle, Reactor};
+pub use reactor::{Diagnostic, Handle, Placement, Reactor};
+
+/// Registration of I/O sources.
+mod registration;
+pub use registration::Registration;
 
-/// TcpStream struct.
-pub use tcp_stream::TcpStream;
-pub mod tcp_stream;
+/// Async wrapper for any mio source.
+mod poll_evented;
+pub use poll_evented::{AsyncIo, PollEvented};
+
+/// Readiness of raw file descriptors.
+#[cfg(unix)]
+mod async_fd;
+#[cfg(unix)]
+pub use async_fd::{AsyncFd, AsyncFdReadyGuard, Readiness};
+
+// Interest used when registering sources in the Reactor.
+pub use mio::Interest;
 
 /// Trait for asychronous reads.
-pub mod async_read;
+mod async_read;
 pub use async_read::AsyncRead;
 
+/// Futures on top of `AsyncRead`.
+mod async_read_ext;
+pub use async_read_ext::{
+    AsyncReadExt, ReadExactFuture, ReadFuture, ReadIntFuture, ReadToEndFuture, ReadToStringFuture,
+};
+
 /// Trait for asynchronous writes.
-pub mod async_write;
+mod async_write;
 pub use async_write::AsyncWrite;
+
+/// Futures on top of `AsyncWrite`.
+mod async_write_ext;
+pub use async_write_ext::{
+    AsyncWriteExt, FlushFuture, ShutdownFuture, WriteAllFuture, WriteFuture, WriteIntFuture,
+    WriteVectoredFuture,
+};
+
+/// Trait for buffered asynchronous reads.
+mod async_buf_read;
+pub use async_buf_read::AsyncBufRead;
+
+/// Futures on top of `AsyncBufRead`.
+mod async_buf_read_ext;
+pub use async_buf_read_ext::{
+    AsyncBufReadExt, FillBufFuture, Lines, ReadLineFuture, ReadUntilFuture,
+};
+
+/// Buffering for readers.
+mod buf_reader;
+pub use buf_reader::BufReader;
+
+/// Buffering for writers.
+mod buf_writer;
+pub use buf_writer::BufWriter;
+
+/// Waiters owned by the I/O futures.
+mod owned_waiters;
+pub(crate) use owned_waiters::OwnedWaiters;
+
+/// In-memory readers and writers for the tests.
+#[cfg(test)]
+mod mock;
@@ -0,0 +1,117 @@
+use crate::io::reactor::Direction;
+use crate::io::{Reactor, WaiterId, Wake};
+use mio::Token;
+use std::cell::RefCell;
+
+thread_local! {
+    // Waiters of the I/O future being polled on this thread, see `OwnedWaiters::scope`.
+    static CURRENT: RefCell<Option<Vec<Registered>>> = const { RefCell::new(None) };
+}
+
+/// Waiter registered in a source on behalf of an I/O future.
+struct Registered {
+    token: Token,
+    dir: Direction,
+    waiter: WaiterId,
+}
+
+/// Waiters owned by an I/O future, removed from their sources once it is dropped.
+///
+/// The future polls its reader or writer inside `OwnedWaiters::scope`, and every
+/// `PollEvented` that has to wait meanwhile registers into it instead of as a task waiter,
+/// so wrappers in between, like `BufReader`, don't need to know about it.
+#[derive(Default)]
+pub(crate) struct OwnedWaiters {
+    registered: Vec<Registered>,
+}
+
+/// Waiter of the I/O future being polled, for one source and direction.
+///
+/// Goes back to the future's `OwnedWaiters` when dropped.
+pub(crate) struct CurrentWaiter {
+    token: Token,
+    dir: Direction,
+    pub(crate) waiter: Option<WaiterId>,
+}
+
+/// Puts the waiters back into their `OwnedWaiters` once `OwnedWaiters::scope` is done.
+struct Restore<'a> {
+    owner: &'a mut OwnedWaiters,
+    previous: Option<Vec<Registered>>,
+}
+
+impl OwnedWaiters {
+    /// Runs `f`, which polls the future's reader or writer, with these waiters as the current ones.
+    pub(crate) fn scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let ours = std::mem::take(&mut self.registered);
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(ours));
+        let _restore = Restore {
+            owner: self,
+            previous,
+        };
+        f()
+    }
+
+    /// Takes the waiter for `token` and `dir` of the future being polled.
+    ///
+    /// `None` outside of `OwnedWaiters::scope`, the caller has no waiter to keep then.
+    pub(crate) fn current(token: Token, dir: Direction) -> Option<CurrentWaiter> {
+        CURRENT.with(|current| {
+            let mut current = current.borrow_mut();
+            let registered = current.as_mut()?;
+            let waiter = registered
+                .iter()
+                .position(|r| r.token == token && r.dir == dir)
+                .map(|pos| registered.swap_remove(pos).waiter);
+            Some(CurrentWaiter { token, dir, waiter })
+        })
+    }
+}
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        let ours = CURRENT
+            .with(|current| std::mem::replace(&mut *current.borrow_mut(), self.previous.take()));
+        self.owner.registered = ours.unwrap_or_default();
+    }
+}
+
+impl Drop for OwnedWaiters {
+    fn drop(&mut self) {
+        for registered in self.registered.drain(..) {
+            Reactor::remove_waiter(
+                registered.token,
+                registered.dir,
+                Wake::All,
+                registered.waiter,
+            );
+        }
+    }
+}
+
+impl Drop for CurrentWaiter {
+    fn drop(&mut self) {
+        let waiter = match self.waiter.take() {
+            Some(waiter) => waiter,
+            None => return,
+        };
+
+        let registered = Registered {
+            token: self.token,
+            dir: self.dir,
+            waiter,
+        };
+        let kept = CURRENT.with(|current| match current.borrow_mut().as_mut() {
+            Some(owned) => {
+                owned.push(registered);
+                None
+            }
+            None => Some(registered),
+        });
+
+        // The scope ended first, nobody would remove it anymore.
+        if let Some(r) = kept {
+            Reactor::remove_waiter(r.token, r.dir, Wake::All, r.waiter);
+        }
+    }
+}
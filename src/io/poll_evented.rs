@@ -0,0 +1,319 @@
+use crate::io::reactor::Direction;
+use crate::io::{OwnedWaiters, Reactor, ReadyEvent, Registration, WaiterId, Wake};
+use mio::event::Source;
+use mio::Interest;
+use std::future::Future;
+use std::io::{ErrorKind, IoSlice, Read, Result as IoResult, Write};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Any mio source, registered in the reactor and made async.
+///
+/// Takes care of the registration, of the readiness, and of the wakers of the tasks
+/// waiting on the source. The crate's own I/O types are built on it.
+pub struct PollEvented<S: Source> {
+    /// Only `None` while `into_inner` takes it out.
+    io: Option<S>,
+    registration: Registration,
+}
+
+impl<S: Source> PollEvented<S> {
+    /// Registers `io` in the reactor, for reading and writing.
+    pub fn new(io: S) -> IoResult<PollEvented<S>> {
+        PollEvented::with_interest(io, Interest::READABLE | Interest::WRITABLE)
+    }
+
+    /// Registers `io` in the reactor, for `interest`.
+    pub fn with_interest(mut io: S, interest: Interest) -> IoResult<PollEvented<S>> {
+        let registration = Registration::new(&mut io, interest)?;
+        Ok(PollEvented {
+            io: Some(io),
+            registration,
+        })
+    }
+
+    /// Obtains a reference to the source.
+    pub fn get_ref(&self) -> &S {
+        self.io.as_ref().expect("source taken out of PollEvented")
+    }
+
+    /// Obtains a mutable reference to the source.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.io.as_mut().expect("source taken out of PollEvented")
+    }
+
+    /// Deregisters the source from the reactor and hands it back.
+    pub fn into_inner(mut self) -> IoResult<S> {
+        let mut io = self.io.take().expect("source taken out of PollEvented");
+        self.registration.deregister(&mut io)?;
+        Ok(io)
+    }
+
+    /// Obtains the registration of the source.
+    pub fn registration(&self) -> &Registration {
+        &self.registration
+    }
+
+    /// Polls for read readiness, registering the waker of `cx` if the source is not readable.
+    ///
+    /// `waiter` is the caller's, see `PollEvented::poll_ready`.
+    pub fn poll_read_ready(
+        &self,
+        cx: &mut Context<'_>,
+        waiter: &mut Option<WaiterId>,
+    ) -> Poll<IoResult<ReadyEvent>> {
+        self.poll_ready(cx, Direction::Read, waiter)
+    }
+
+    /// Polls for write readiness, registering the waker of `cx` if the source is not writable.
+    ///
+    /// `waiter` is the caller's, see `PollEvented::poll_ready`.
+    pub fn poll_write_ready(
+        &self,
+        cx: &mut Context<'_>,
+        waiter: &mut Option<WaiterId>,
+    ) -> Poll<IoResult<ReadyEvent>> {
+        self.poll_ready(cx, Direction::Write, waiter)
+    }
+
+    /// Polls for readiness in `dir`, for the caller's own `waiter`.
+    ///
+    /// Whoever holds the waiter has to hand it to `PollEvented::remove_waiter` if
+    /// it stops polling before getting readiness, see `Reactor::remove_waiter`.
+    pub fn poll_ready(
+        &self,
+        cx: &mut Context<'_>,
+        dir: Direction,
+        waiter: &mut Option<WaiterId>,
+    ) -> Poll<IoResult<ReadyEvent>> {
+        Reactor::poll_ready(cx, self.registration.token(), dir, Wake::All, waiter)
+    }
+
+    /// Clears readiness returned by one of the `poll_*_ready` methods,
+    /// once the I/O operation returned `WouldBlock`.
+    pub fn clear_readiness(&self, event: ReadyEvent) {
+        Reactor::clear_readiness(self.registration.token(), event)
+    }
+
+    /// Removes a waiter registered by `PollEvented::poll_ready`.
+    pub fn remove_waiter(&self, dir: Direction, waiter: WaiterId) {
+        Reactor::remove_waiter(self.registration.token(), dir, Wake::All, waiter)
+    }
+
+    /// Runs `f` on the source once it is ready in `dir`, until it does not return `WouldBlock`.
+    ///
+    /// `waiter` is the caller's, see `PollEvented::poll_ready`.
+    pub fn poll_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        dir: Direction,
+        waiter: &mut Option<WaiterId>,
+        f: impl FnMut(&S) -> IoResult<R>,
+    ) -> Poll<IoResult<R>> {
+        self.poll_io_for(cx, dir, Some(waiter), f)
+    }
+
+    /// Runs `f` on the source once it is readable, until it does not return `WouldBlock`.
+    ///
+    /// For callers without a waiter of their own: every task polling gets woken,
+    /// its waker stays registered until the source gets readable.
+    /// Polled from the crate's I/O futures, the future owns the waiter instead and removes it once dropped.
+    pub fn poll_read_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnMut(&S) -> IoResult<R>,
+    ) -> Poll<IoResult<R>> {
+        self.poll_io_for(cx, Direction::Read, None, f)
+    }
+
+    /// Runs `f` on the source once it is writable, same as `PollEvented::poll_read_io`.
+    pub fn poll_write_io<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnMut(&S) -> IoResult<R>,
+    ) -> Poll<IoResult<R>> {
+        self.poll_io_for(cx, Direction::Write, None, f)
+    }
+
+    /// Reads into `buf` once the source is readable, see `PollEvented::poll_read_io`.
+    pub fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IoResult<usize>>
+    where
+        for<'a> &'a S: Read,
+    {
+        self.poll_read_io(cx, |mut io| io.read(buf))
+    }
+
+    /// Writes `buf` once the source is writable, see `PollEvented::poll_write_io`.
+    pub fn poll_write(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<IoResult<usize>>
+    where
+        for<'a> &'a S: Write,
+    {
+        self.poll_write_io(cx, |mut io| io.write(buf))
+    }
+
+    /// Same as `PollEvented::poll_write`, writing from several buffers at once.
+    pub fn poll_write_vectored(
+        &self,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<IoResult<usize>>
+    where
+        for<'a> &'a S: Write,
+    {
+        self.poll_write_io(cx, |mut io| io.write_vectored(bufs))
+    }
+
+    /// Runs `f` once the source is ready, waiting on `waiter` or as a task waiter if there is none.
+    fn poll_io_for<R>(
+        &self,
+        cx: &mut Context<'_>,
+        dir: Direction,
+        mut waiter: Option<&mut Option<WaiterId>>,
+        mut f: impl FnMut(&S) -> IoResult<R>,
+    ) -> Poll<IoResult<R>> {
+        let token = self.registration.token();
+        loop {
+            // Skips the syscall entirely while the source is known not to be ready.
+            let event = ready!(match waiter.as_deref_mut() {
+                Some(waiter) => self.poll_ready(cx, dir, waiter),
+                // Polled by one of the crate's I/O futures, which removes the waiter once dropped.
+                None => match OwnedWaiters::current(token, dir) {
+                    Some(mut current) => {
+                        Reactor::poll_ready(cx, token, dir, Wake::All, &mut current.waiter)
+                    }
+                    None => Reactor::poll_ready_task(cx, token, dir),
+                },
+            })?;
+
+            match f(self.get_ref()) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => self.clear_readiness(event),
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    /// Runs `f` on the source once it is ready for `interest`, until it does not return `WouldBlock`.
+    ///
+    /// Waits on every direction of `interest`, `f` runs as soon as any of them is ready.
+    ///
+    /// Cancel safe as long as `f` is: it only runs in the poll that returns its result.
+    pub fn async_io<R, F>(&self, interest: Interest, f: F) -> AsyncIo<'_, S, F>
+    where
+        F: FnMut(&S) -> IoResult<R> + Unpin,
+    {
+        // Priority only exists on some platforms, read instead like the other waits do.
+        let interest = match Direction::from_interest(interest).next() {
+            Some(_) => interest,
+            None => Interest::READABLE,
+        };
+
+        AsyncIo {
+            evented: self,
+            interest,
+            f,
+            waiters: [None; 3],
+        }
+    }
+}
+
+impl<S: Source> Drop for PollEvented<S> {
+    /// Deregisters the source from the reactor.
+    fn drop(&mut self) {
+        if let Some(mut io) = self.io.take() {
+            let _ = self.registration.deregister(&mut io);
+        }
+    }
+}
+
+/// Future of `PollEvented::async_io`.
+pub struct AsyncIo<'o, S: Source, F> {
+    evented: &'o PollEvented<S>,
+    interest: Interest,
+    f: F,
+    /// Waiter of each direction, in the order of `DIRECTIONS`.
+    waiters: [Option<WaiterId>; 3],
+}
+
+/// Directions `AsyncIo` can wait on.
+const DIRECTIONS: [Direction; 3] = [Direction::Read, Direction::Write, Direction::Priority];
+
+fn slot(dir: Direction) -> usize {
+    match dir {
+        Direction::Read => 0,
+        Direction::Write => 1,
+        Direction::Priority => 2,
+    }
+}
+
+impl<S, F, R> Future for AsyncIo<'_, S, F>
+where
+    S: Source,
+    F: FnMut(&S) -> IoResult<R> + Unpin,
+{
+    type Output = IoResult<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        loop {
+            // Every direction not ready yet keeps a waiter, whichever gets ready first wakes us.
+            let mut ready = None;
+            for dir in Direction::from_interest(future.interest) {
+                let waiter = &mut future.waiters[slot(dir)];
+                if let Poll::Ready(event) = future.evented.poll_ready(cx, dir, waiter) {
+                    ready = Some(event?);
+                    break;
+                }
+            }
+
+            let event = match ready {
+                Some(event) => event,
+                None => return Poll::Pending,
+            };
+            match (future.f)(future.evented.get_ref()) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    future.evented.clear_readiness(event)
+                }
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+}
+
+impl<S: Source, F> Drop for AsyncIo<'_, S, F> {
+    fn drop(&mut self) {
+        for (dir, waiter) in DIRECTIONS.into_iter().zip(&mut self.waiters) {
+            if let Some(waiter) = waiter.take() {
+                self.evented.remove_waiter(dir, waiter);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix, feature = "rt", feature = "net"))]
+mod tests {
+    use super::PollEvented;
+    use crate::runtime::{Flavor, Runtime};
+    use mio::net::UnixStream;
+    use mio::Interest;
+    use std::io::Write;
+    use std::time::Duration;
+
+    #[test]
+    fn async_io_resolves_on_any_direction() {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        let (a, _b) = UnixStream::pair().unwrap();
+        let a = PollEvented::new(a).unwrap();
+
+        // Nothing to read, so only the write side can get ready.
+        let written = runtime.block_on_timeout(
+            async move {
+                a.async_io(Interest::READABLE | Interest::WRITABLE, |mut io| {
+                    io.write(b"x")
+                })
+                .await
+            },
+            Duration::from_secs(5),
+        );
+        assert_eq!(written.unwrap().unwrap(), 1);
+    }
+}
@@ -1,22 +1,147 @@
 // I/O Reactor
-use crate::io::IoSource;
+use crate::io::iosource::{WaiterId, Wake};
+use crate::io::sources::{reactor_of, Sources, MAX_REACTORS, WAKE_TOKEN};
+use crate::io::{Ready, ReadyEvent};
 use async_lock::OnceCell;
 use mio::event::Source;
 use mio::{Events, Interest, Poll, Registry, Token};
-use slab::Slab;
-use std::io::Result as IoResult;
-use std::sync::{Arc, Mutex};
-use std::task::Context;
+use std::cell::Cell;
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, RawFd};
+#[cfg(feature = "rt-drive-io")]
+use std::sync::atomic::{self, AtomicBool, AtomicU32};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::task::{Context, Poll as TaskPoll, Waker};
+use std::time::Duration;
+
+/// Times in a row a transient poll error is retried before the reactor gives up.
+const MAX_POLL_RETRIES: u32 = 5;
+
+/// Delay before retrying a failed poll, multiplied by the retry count.
+const POLL_RETRY_DELAY: Duration = Duration::from_millis(10);
 
 /// represents the interest of the underlying io.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Read,
     Write,
+    Priority,
+}
+
+impl Direction {
+    /// Readiness flags this direction waits on.
+    ///
+    /// Closing and errors are included, so waiters find out about them.
+    pub fn mask(self) -> Ready {
+        match self {
+            Direction::Read => Ready::READABLE | Ready::READ_CLOSED | Ready::ERROR,
+            Direction::Write => Ready::WRITABLE | Ready::WRITE_CLOSED | Ready::ERROR,
+            Direction::Priority => Ready::PRIORITY | Ready::ERROR,
+        }
+    }
+
+    /// Directions covered by `interest`.
+    pub fn from_interest(interest: Interest) -> impl Iterator<Item = Direction> {
+        let priority = Direction::interest_is_priority(interest);
+        [
+            (interest.is_readable(), Direction::Read),
+            (interest.is_writable(), Direction::Write),
+            (priority, Direction::Priority),
+        ]
+        .into_iter()
+        .filter_map(|(wanted, dir)| wanted.then_some(dir))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn interest_is_priority(interest: Interest) -> bool {
+        interest.is_priority()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn interest_is_priority(_: Interest) -> bool {
+        false
+    }
+}
+
+/// Something that went wrong in a reactor, handed to the handler set by `Reactor::set_diagnostics`.
+#[derive(Debug)]
+pub enum Diagnostic<'e> {
+    /// Polling failed for a moment and is retried after a short delay, `attempt` counts from 1.
+    PollRetry { error: &'e IoError, attempt: u32 },
+
+    /// Interrupting the thread blocked in the poll failed, it only sees new work on its next event.
+    WakeFailed(&'e IoError),
+
+    /// Polling failed for good, I/O fails from then on, see `Reactor::failure`.
+    Failed(&'e IoError),
+}
+
+/// Handler for the diagnostics of every reactor.
+type DiagnosticsHandler = Box<dyn Fn(Diagnostic<'_>) + Send + Sync>;
+
+static DIAGNOSTICS: RwLock<Option<DiagnosticsHandler>> = RwLock::new(None);
+
+/// How `Reactor::register` picks the reactor of a new source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Sources go to every reactor in turn.
+    RoundRobin,
+
+    /// Sources go to the reactor of the worker thread registering them,
+    /// worker `n` uses reactor `n % count`. Other threads go round-robin.
+    Worker,
+}
+
+thread_local! {
+    // Worker thread index of this thread, for `Placement::Worker`.
+    static WORKER: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Reactors set up by `Reactor::init` or `Reactor::init_embedded`.
+static CONFIG: OnceLock<Setup> = OnceLock::new();
+
+/// How the global reactors are set up.
+#[derive(Clone, Copy)]
+struct Setup {
+    count: usize,
+    placement: Placement,
+
+    /// Whether every reactor gets a thread polling it, see `Reactor::init_embedded`.
+    #[cfg_attr(feature = "rt-drive-io", allow(dead_code))]
+    poll_threads: bool,
 }
 
-/// Represents the global I/O Reactor.
+/// Every reactor, and how sources are spread across them.
+struct Reactors {
+    reactors: Box<[Reactor]>,
+    placement: Placement,
+
+    /// Reactor of the next source, for `Placement::RoundRobin`.
+    next: AtomicUsize,
+}
+
+static REACTORS: OnceCell<Reactors> = OnceCell::new();
+
+impl Reactors {
+    /// Picks the reactor of a new source.
+    fn pick(&self) -> &Reactor {
+        let worker = match self.placement {
+            Placement::Worker => WORKER.with(Cell::get),
+            Placement::RoundRobin => None,
+        };
+
+        let index = worker.unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed));
+        &self.reactors[index % self.reactors.len()]
+    }
+}
+
+/// Represents one of the global I/O Reactors.
 ///
-/// Only one exists at anytime.
+/// There is one by default, see `Reactor::init` for more.
+/// Each has its own poll and its own table of sources,
+/// and the token of a source tells which reactor it belongs to.
 pub struct Reactor {
     /// Re-usable event pool.
     events: Arc<Mutex<Events>>,
@@ -25,16 +150,78 @@ pub struct Reactor {
     handle: Arc<Handle>,
 
     /// I/O sources
-    sources: Arc<Mutex<Slab<IoSource>>>,
+    sources: Arc<Sources>,
+
+    /// Set once polling the reactor failed for good, I/O fails with it from then on.
+    failure: Arc<OnceLock<Failure>>,
+
+    /// Transient poll errors in a row, for `Reactor::drive`.
+    #[cfg(feature = "rt-drive-io")]
+    retries: AtomicU32,
+}
+
+/// Error polling the reactor failed with.
+///
+/// `std::io::Error` is not `Clone`, so its kind and message are kept
+/// to hand out a fresh error to every failing operation.
+struct Failure {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl From<IoError> for Failure {
+    fn from(e: IoError) -> Failure {
+        Failure {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl Failure {
+    fn to_error(&self) -> IoError {
+        IoError::new(self.kind, format!("I/O reactor failed: {}", self.message))
+    }
+}
+
+/// Marks the reactor as failed if the polling thread exits,
+/// whether it broke out of its loop or panicked.
+#[cfg(not(feature = "rt-drive-io"))]
+struct PollThreadGuard {
+    sources: Arc<Sources>,
+    failure: Arc<OnceLock<Failure>>,
+    error: Option<IoError>,
+}
+
+#[cfg(not(feature = "rt-drive-io"))]
+impl Drop for PollThreadGuard {
+    fn drop(&mut self) {
+        let failure = match self.error.take() {
+            Some(e) => Failure::from(e),
+            None => Failure {
+                kind: ErrorKind::Other,
+                message: String::from("polling thread panicked"),
+            },
+        };
+        Reactor::fail(&self.sources, &self.failure, failure);
+    }
 }
 
 /// Handle to the I/O Reactor.
+///
+/// On unix, its fd becomes readable once the reactor has events to hand out,
+/// see `Reactor::turn`. Only the reactors set up by `Reactor::init_embedded`,
+/// or all of them with the `rt-drive-io` feature, are not taken by a polling thread.
 pub struct Handle {
     /// Registry belonging to `mio::Poll`
     registry: Registry,
 
     /// Poll from which we obtain events.
     poll: Mutex<Poll>,
+
+    /// Interrupts whoever is blocked in `poll`.
+    #[cfg(feature = "rt-drive-io")]
+    waker: mio::Waker,
 }
 
 impl Handle {
@@ -45,119 +232,711 @@ impl Handle {
     fn arc_new(registry: Registry, poll: Poll) -> Arc<Handle> {
         Arc::new(Handle {
             registry,
+            #[cfg(feature = "rt-drive-io")]
+            waker: mio::Waker::new(poll.registry(), WAKE_TOKEN).expect("waker create fail"),
             poll: Mutex::new(poll),
         })
     }
 }
 
+#[cfg(unix)]
+impl AsRawFd for Handle {
+    /// The fd of the `mio::Poll`, which is an epoll or kqueue instance.
+    fn as_raw_fd(&self) -> RawFd {
+        self.registry.as_raw_fd()
+    }
+}
+
 impl Reactor {
     /// Create a new Reactor
     pub fn new() -> (Reactor, Arc<Handle>) {
+        Reactor::with_index(0)
+    }
+
+    /// Creates the reactor at `index` of the global reactors.
+    fn with_index(index: usize) -> (Reactor, Arc<Handle>) {
         let poll = Poll::new().expect("poll create fail");
         let events = Arc::new(Mutex::new(Events::with_capacity(1024)));
         let registry = poll.registry().try_clone().expect("registry clone fail");
-        let sources = Arc::new(Mutex::new(Slab::with_capacity(1024)));
+        let sources = Arc::new(Sources::new(index));
 
         let handle = Handle::arc_new(registry, poll);
         let r = Reactor {
             sources,
             events,
             handle,
+            failure: Arc::new(OnceLock::new()),
+            #[cfg(feature = "rt-drive-io")]
+            retries: AtomicU32::new(0),
         };
         let arc_handle = Arc::clone(&r.handle);
         (r, arc_handle)
     }
 
-    /// Get reference to the global Reactor instance.
-    pub fn get() -> &'static Reactor {
-        static REACTOR: OnceCell<Reactor> = OnceCell::new();
+    /// Sets up `count` reactors, spreading the sources across them as `placement` says.
+    ///
+    /// Has to be called before the reactors are first used, fails otherwise.
+    /// At most 16 reactors are supported. With the `rt-drive-io` feature only one is,
+    /// a thread waiting in the poll of one reactor would miss the events of the others.
+    pub fn init(count: usize, placement: Placement) -> IoResult<()> {
+        if count == 0 || count > MAX_REACTORS {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("there can be between 1 and {MAX_REACTORS} reactors"),
+            ));
+        }
 
-        REACTOR.get_or_init_blocking(|| {
-            let (reactor, handle) = Reactor::new();
+        #[cfg(feature = "rt-drive-io")]
+        if count > 1 {
+            return Err(IoError::new(
+                ErrorKind::Unsupported,
+                "multiple reactors need their own polling threads, which rt-drive-io removes",
+            ));
+        }
 
-            // Polling thread
-            let arc_events = Arc::clone(&reactor.events);
-            let arc_sources = Arc::clone(&reactor.sources);
+        Reactor::setup(Setup {
+            count,
+            placement,
+            poll_threads: true,
+        })
+    }
 
-            std::thread::spawn(move || {
-                let mut poll = handle.poll.lock().expect("failed loop poll lock");
-                let mut events = arc_events.lock().expect("event lock fail");
+    /// Sets up a single reactor without a thread polling it, so a foreign event loop
+    /// can drive it through `Reactor::turn`, see `Handle`.
+    ///
+    /// Has to be called before the reactor is first used, fails otherwise.
+    /// Nothing else polls the reactor then, I/O only makes progress while the loop turns it.
+    /// With the `rt-drive-io` feature there is no polling thread anyway, but idle runtime
+    /// threads keep polling the reactor.
+    pub fn init_embedded() -> IoResult<()> {
+        Reactor::setup(Setup {
+            count: 1,
+            placement: Placement::RoundRobin,
+            poll_threads: false,
+        })
+    }
 
-                loop {
-                    match poll.poll(&mut events, None) {
-                        Ok(_) => {}
-                        Err(e) => panic!("Error: {:?}", e),
+    fn setup(setup: Setup) -> IoResult<()> {
+        if REACTORS.get().is_some() || CONFIG.set(setup).is_err() {
+            return Err(IoError::other("the reactors are already set up"));
+        }
+        Ok(())
+    }
+
+    /// Obtains every global reactor, creating them on first use.
+    ///
+    /// With the `rt-drive-io` feature there is no polling thread,
+    /// idle runtime threads poll the reactor through `Reactor::drive` instead.
+    pub fn all() -> &'static [Reactor] {
+        &Reactor::state().reactors
+    }
+
+    /// Obtains the global reactors along with their placement, creating them on first use.
+    fn state() -> &'static Reactors {
+        REACTORS.get_or_init_blocking(|| {
+            let setup = *CONFIG.get_or_init(|| Setup {
+                count: 1,
+                placement: Placement::RoundRobin,
+                poll_threads: true,
+            });
+            let reactors = (0..setup.count)
+                .map(|index| {
+                    let (reactor, _handle) = Reactor::with_index(index);
+
+                    #[cfg(not(feature = "rt-drive-io"))]
+                    if setup.poll_threads {
+                        reactor.spawn_poll_thread(_handle);
                     }
 
-                    for event in events.iter() {
-                        println!("{:?}", event);
-                        let srcs = arc_sources.lock().expect("sources lock in loop failed!");
-
-                        let src = match srcs.get(event.token().0) {
-                            None => panic!(
-                                "Received event for token {}, but no such source is present.",
-                                event.token().0
-                            ),
-                            Some(source) => source,
-                        };
-
-                        if src.has_wakers() {
-                            src.wake_with_event(event)
-                        }
+                    reactor
+                })
+                .collect();
+
+            Reactors {
+                reactors,
+                placement: setup.placement,
+                next: AtomicUsize::new(0),
+            }
+        })
+    }
+
+    /// Get reference to the first global Reactor instance.
+    pub fn get() -> &'static Reactor {
+        &Reactor::all()[0]
+    }
+
+    /// Obtains the reactor `token` was handed out by.
+    fn of(token: Token) -> &'static Reactor {
+        let reactors = Reactor::all();
+        &reactors[reactor_of(token) % reactors.len()]
+    }
+
+    /// Marks the calling thread as worker `index`, for `Placement::Worker`.
+    #[cfg(feature = "rt-multi-thread")]
+    pub(crate) fn set_worker(index: usize) {
+        WORKER.with(|worker| worker.set(Some(index)));
+    }
+
+    /// Obtains the handle of this reactor.
+    pub fn handle(&self) -> &Arc<Handle> {
+        &self.handle
+    }
+
+    /// Spawns the thread polling the reactor for as long as the program runs.
+    #[cfg(not(feature = "rt-drive-io"))]
+    fn spawn_poll_thread(&self, handle: Arc<Handle>) {
+        let arc_events = Arc::clone(&self.events);
+        let arc_sources = Arc::clone(&self.sources);
+        let mut guard = PollThreadGuard {
+            sources: Arc::clone(&self.sources),
+            failure: Arc::clone(&self.failure),
+            error: None,
+        };
+
+        std::thread::spawn(move || {
+            let mut poll = handle.poll.lock().expect("failed loop poll lock");
+            let mut events = arc_events.lock().expect("event lock fail");
+            let mut wakers = Vec::new();
+            let mut retries = 0;
+
+            loop {
+                match poll.poll(&mut events, None) {
+                    Ok(_) => retries = 0,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) if Reactor::is_transient(&e) && retries < MAX_POLL_RETRIES => {
+                        retries += 1;
+                        Reactor::report(Diagnostic::PollRetry {
+                            error: &e,
+                            attempt: retries,
+                        });
+                        std::thread::sleep(POLL_RETRY_DELAY * retries);
+                        continue;
+                    }
+                    Err(e) => {
+                        guard.error = Some(e);
+                        break;
                     }
                 }
-            });
 
-            reactor
-        })
+                Reactor::dispatch(&arc_sources, &events, &mut wakers);
+            }
+
+            drop(guard);
+        });
     }
 
-    /// Obtains handle from a reactor.
+    /// Sets the readiness of the sources `events` came in for, and wakes their waiters.
+    ///
+    /// Returns the amount of events that were for a source.
+    fn dispatch(sources: &Sources, events: &Events, wakers: &mut Vec<Waker>) -> usize {
+        let mut handled = 0;
+        for event in events.iter() {
+            // Only there to interrupt the poll.
+            if event.token() == WAKE_TOKEN {
+                continue;
+            }
+
+            // Late event for a source that was deregistered, or whose slot got reused.
+            let src = match sources.get(event.token()) {
+                None => continue,
+                Some(source) => source,
+            };
+
+            let ready = Ready::from_event(event);
+            let mut waiters = src.waiters();
+            src.set_readiness(ready);
+            if !waiters.is_empty() {
+                waiters.wake_ready(ready, wakers)
+            }
+
+            drop(waiters);
+            drop(src);
+            wakers.drain(..).for_each(Waker::wake);
+            handled += 1;
+        }
+
+        handled
+    }
+
+    /// Marks the reactor as failed, and wakes every waiter to find out about it.
+    ///
+    /// The failure is set before the waiters are taken, and waiters are only added
+    /// after checking for it under the same lock, so none of them gets left behind.
+    fn fail(sources: &Sources, cell: &OnceLock<Failure>, failure: Failure) {
+        Reactor::report(Diagnostic::Failed(&failure.to_error()));
+        let _ = cell.set(failure);
+
+        let all = sources.all();
+        let mut wakers = Vec::new();
+        for src in all.iter() {
+            let mut waiters = src.waiters();
+            for dir in [Direction::Read, Direction::Write, Direction::Priority] {
+                waiters.wake(dir, &mut wakers);
+            }
+        }
+
+        drop(all);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Polls the reactor once from the calling thread, waking the waiters of the events
+    /// that came in, if no other thread is polling it already.
+    ///
+    /// `parked` is set while the thread is blocked in the poll,
+    /// whoever hands it work then calls `Reactor::unpark` to interrupt it.
+    /// `idle` is checked once `parked` is set, the poll is skipped if it returns `false`,
+    /// since work showed up in the meantime.
+    ///
+    /// Returns `false` if the thread did not get to poll the reactor,
+    /// because another thread is polling it or it failed.
+    #[cfg(feature = "rt-drive-io")]
+    pub(crate) fn drive(
+        timeout: Option<Duration>,
+        parked: &AtomicBool,
+        idle: impl FnOnce() -> bool,
+    ) -> bool {
+        let reactor = Reactor::get();
+        if reactor.failure.get().is_some() {
+            return false;
+        }
+
+        let mut poll = match reactor.handle.poll.try_lock() {
+            Ok(poll) => poll,
+            Err(_) => return false,
+        };
+        let mut events = reactor.events.lock().expect("event lock fail");
+
+        // Pairs with the fence in `Reactor::unpark`,
+        // either we see the work or they see us parked.
+        parked.store(true, Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+        if !idle() {
+            parked.store(false, Ordering::Relaxed);
+            return true;
+        }
+
+        let result = poll.poll(&mut events, timeout);
+        parked.store(false, Ordering::Relaxed);
+
+        match result {
+            Ok(_) => reactor.retries.store(0, Ordering::Relaxed),
+            Err(e) if e.kind() == ErrorKind::Interrupted => return true,
+            Err(e) if Reactor::is_transient(&e) => {
+                let retries = reactor.retries.fetch_add(1, Ordering::Relaxed) + 1;
+                if retries <= MAX_POLL_RETRIES {
+                    Reactor::report(Diagnostic::PollRetry {
+                        error: &e,
+                        attempt: retries,
+                    });
+                    drop(events);
+                    drop(poll);
+                    std::thread::sleep(POLL_RETRY_DELAY * retries);
+                    return true;
+                }
+
+                Reactor::fail(&reactor.sources, &reactor.failure, Failure::from(e));
+                return false;
+            }
+            Err(e) => {
+                Reactor::fail(&reactor.sources, &reactor.failure, Failure::from(e));
+                return false;
+            }
+        }
+
+        let mut wakers = Vec::new();
+        Reactor::dispatch(&reactor.sources, &events, &mut wakers);
+        true
+    }
+
+    /// Polls the reactor once from the calling thread, waking the waiters of the events
+    /// that came in, for driving it from a foreign event loop.
+    ///
+    /// Blocks for up to `timeout`, `None` blocks until an event comes in.
+    /// The loop can wait for the fd of `Handle` to become readable first,
+    /// and then turn with a zero timeout.
+    ///
+    /// Fails with `WouldBlock` if another thread is polling the reactor: its polling thread,
+    /// unless it was set up by `Reactor::init_embedded`, or an idle runtime thread with the
+    /// `rt-drive-io` feature. Poll errors are returned as they are, they do not fail the reactor.
+    /// Fails with `Unsupported` if `Reactor::init` set up more than one reactor,
+    /// a single turn could only drive one of them.
+    ///
+    /// Returns the amount of events that came in for a source.
+    pub fn turn(timeout: Option<Duration>) -> IoResult<usize> {
+        let reactor = match Reactor::all() {
+            [reactor] => reactor,
+            _ => {
+                return Err(IoError::new(
+                    ErrorKind::Unsupported,
+                    "only a single reactor can be turned, see `Reactor::init_embedded`",
+                ))
+            }
+        };
+        if let Some(failure) = reactor.failure.get() {
+            return Err(failure.to_error());
+        }
+
+        let mut poll = match reactor.handle.poll.try_lock() {
+            Ok(poll) => poll,
+            Err(_) => {
+                return Err(IoError::new(
+                    ErrorKind::WouldBlock,
+                    "the I/O reactor is polled by another thread",
+                ))
+            }
+        };
+        let mut events = reactor.events.lock().expect("event lock fail");
+
+        match poll.poll(&mut events, timeout) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => return Ok(0),
+            Err(e) => return Err(e),
+        }
+
+        let mut wakers = Vec::new();
+        Ok(Reactor::dispatch(&reactor.sources, &events, &mut wakers))
+    }
+
+    /// Interrupts the thread blocked in `Reactor::drive`, if `parked` says there is one.
+    ///
+    /// Called after handing it work, so it gets to the work right away.
+    #[cfg(feature = "rt-drive-io")]
+    pub(crate) fn unpark(parked: &AtomicBool) {
+        atomic::fence(Ordering::SeqCst);
+        if !parked.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = Reactor::get().handle.waker.wake() {
+            Reactor::report(Diagnostic::WakeFailed(&e));
+        }
+    }
+
+    /// Obtains the handle of the first reactor.
     pub fn get_handle() -> Arc<Handle> {
         Reactor::get().handle.clone()
     }
 
-    /// Registers a IO source in the reactor.
-    pub fn register(src: &mut impl Source, interest: Interest) -> IoResult<()> {
-        let mut sources = Reactor::get().sources.lock().expect("failed source lock");
-        let token = sources.vacant_key();
+    /// Obtains the registry of the first global Reactor.
+    pub fn registry() -> &'static Registry {
+        Reactor::get().handle.registry()
+    }
 
-        Reactor::get_handle()
-            .registry
-            .register(src, Token(token), interest)?;
+    /// Registers a IO source in one of the reactors, see `Placement`.
+    ///
+    /// Returns the token the source was registered with.
+    pub fn register(src: &mut impl Source, interest: Interest) -> IoResult<Token> {
+        let reactor = Reactor::state().pick();
+        if let Some(failure) = reactor.failure.get() {
+            return Err(failure.to_error());
+        }
 
-        let _key = sources.insert(IoSource::new(token));
-        Ok(())
+        reactor
+            .sources
+            .insert(|token| reactor.handle.registry.register(src, token, interest))
+    }
+
+    /// Deregisters a IO source from the reactor, and forgets about it.
+    ///
+    /// Events still in flight for its token are ignored,
+    /// even if the slot gets reused by another source.
+    pub fn deregister(src: &mut impl Source, token: Token) -> IoResult<()> {
+        Reactor::forget(token);
+        Reactor::of(token).handle.registry.deregister(src)
+    }
+
+    /// Forgets about the source of `token`, without deregistering it from the poll.
+    ///
+    /// For sources that are about to be closed, which removes them from the poll.
+    pub fn forget(token: Token) {
+        // Its waiters' wakers may be the last references to their tasks,
+        // they are dropped here, outside of the table's lock.
+        drop(Reactor::of(token).sources.remove(token));
     }
 
     /// Reregisters a IO source in the reactor.
-    pub fn reregister(src: &mut impl Source, token: usize, intr: Interest) -> IoResult<()> {
-        //let sources = Reactor::get().sources.lock().expect("failed sources lock!");
-        Reactor::get_handle()
+    pub fn reregister(src: &mut impl Source, token: Token, intr: Interest) -> IoResult<()> {
+        Reactor::of(token)
+            .handle
             .registry
-            .reregister(src, Token(token), intr)
+            .reregister(src, token, intr)
+    }
+
+    /// Error a reactor failed with, if polling one failed for good.
+    ///
+    /// I/O on its sources can not make progress anymore then.
+    /// Reactors that were never started did not fail, so it does not start them.
+    pub fn failure() -> Option<IoError> {
+        REACTORS
+            .get()?
+            .reactors
+            .iter()
+            .find_map(|reactor| reactor.failure.get())
+            .map(Failure::to_error)
+    }
+
+    /// Sets the handler for the diagnostics of every reactor, replacing the previous one.
+    ///
+    /// It runs on whichever thread polls the reactor, so it should not block.
+    /// Without a handler, diagnostics are dropped.
+    pub fn set_diagnostics(handler: impl Fn(Diagnostic<'_>) + Send + Sync + 'static) {
+        *DIAGNOSTICS.write().expect("failed diagnostics lock") = Some(Box::new(handler));
+    }
+
+    /// Hands `diagnostic` to the handler set by `Reactor::set_diagnostics`.
+    fn report(diagnostic: Diagnostic<'_>) {
+        let handler = match DIAGNOSTICS.read() {
+            Ok(handler) => handler,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(handler) = handler.as_ref() {
+            handler(diagnostic)
+        }
+    }
+
+    /// Poll errors worth retrying, the system ran short of resources for a moment.
+    fn is_transient(e: &IoError) -> bool {
+        matches!(e.kind(), ErrorKind::OutOfMemory | ErrorKind::WouldBlock)
     }
 
-    pub fn attach_waker(cx: &mut Context<'_>, token: Token, dir: Direction) {
-        let mut sources = Reactor::get().sources.lock().expect("failed sources lock!");
-        let src = match sources.get_mut(token.0) {
+    /// Checks if the source is ready in the given direction,
+    /// registering the waker of `cx` as a waiter if it is not.
+    ///
+    /// Both happen under the waiters lock of the source, which the reactor thread
+    /// takes to set readiness, so a wakeup can not slip in between them.
+    ///
+    /// `waiter` holds the caller's registration, see `IoSource::add_waiter`.
+    ///
+    /// Fails once the reactor failed, since readiness would never come,
+    /// and with `NotFound` if the source of `token` is not registered anymore.
+    pub fn poll_ready(
+        cx: &mut Context<'_>,
+        token: Token,
+        dir: Direction,
+        wake: Wake,
+        waiter: &mut Option<WaiterId>,
+    ) -> TaskPoll<IoResult<ReadyEvent>> {
+        Reactor::poll_ready_mask(cx, token, dir, dir.mask(), wake, waiter)
+    }
+
+    /// Same as `Reactor::poll_ready`, but only the readiness flags in `mask` count.
+    ///
+    /// The waiter is still woken for any readiness of `dir`.
+    pub fn poll_ready_mask(
+        cx: &mut Context<'_>,
+        token: Token,
+        dir: Direction,
+        mask: Ready,
+        wake: Wake,
+        waiter: &mut Option<WaiterId>,
+    ) -> TaskPoll<IoResult<ReadyEvent>> {
+        Reactor::poll_ready_for(cx, token, dir, mask, wake, Some(waiter))
+    }
+
+    /// Same as `Reactor::poll_ready`, for callers without a waiter of their own.
+    ///
+    /// Every task polling gets woken, its waker stays registered
+    /// until the source gets ready in `dir`, see `Waiters::add_task_waiter`.
+    pub fn poll_ready_task(
+        cx: &mut Context<'_>,
+        token: Token,
+        dir: Direction,
+    ) -> TaskPoll<IoResult<ReadyEvent>> {
+        Reactor::poll_ready_for(cx, token, dir, dir.mask(), Wake::All, None)
+    }
+
+    /// Polls for readiness, registering `waiter` or a task waiter if there is none.
+    fn poll_ready_for(
+        cx: &mut Context<'_>,
+        token: Token,
+        dir: Direction,
+        mask: Ready,
+        wake: Wake,
+        waiter: Option<&mut Option<WaiterId>>,
+    ) -> TaskPoll<IoResult<ReadyEvent>> {
+        let reactor = Reactor::of(token);
+        // Deregistered, or its slot got reused by another source.
+        let src = match reactor.sources.get(token) {
             Some(source) => source,
-            None => panic!("Trying to attach waker to an unregistered source!"),
+            None => {
+                return TaskPoll::Ready(Err(IoError::new(
+                    ErrorKind::NotFound,
+                    "the source is not registered in the reactor",
+                )))
+            }
         };
 
-        match dir {
-            Direction::Read => {
-                let cur_waker = src.get_read_waker();
-                match cur_waker {
-                    None => src.change_read_waker(cx.waker()),
-                    Some(waker) => {
-                        if !waker.will_wake(cx.waker()) {
-                            src.change_read_waker(cx.waker());
-                        }
-                    }
-                }
+        let mut waiters = src.waiters();
+        if let Some(failure) = reactor.failure.get() {
+            return TaskPoll::Ready(Err(failure.to_error()));
+        }
+
+        let event = src.readiness();
+        let ready = event.ready & mask;
+        if !ready.is_empty() {
+            let next = match wake {
+                Wake::One => waiters.wake_next(dir),
+                Wake::All => None,
+            };
+
+            drop(waiters);
+            if let Some(waker) = next {
+                waker.wake()
             }
-            Direction::Write => src.change_write_waker(cx.waker()),
+            return TaskPoll::Ready(Ok(ReadyEvent { ready, ..event }));
+        }
+
+        match waiter {
+            Some(waiter) => waiters.add_waiter(dir, cx.waker(), wake, waiter),
+            None => waiters.add_task_waiter(dir, cx.waker()),
         }
+        TaskPoll::Pending
+    }
+
+    /// Removes a waiter registered by `Reactor::poll_ready`.
+    ///
+    /// I/O futures call this when dropped, so a cancelled future is not woken later
+    /// and its waker does not keep the task alive.
+    pub fn remove_waiter(token: Token, dir: Direction, wake: Wake, waiter: WaiterId) {
+        let src = match Reactor::of(token).sources.get(token) {
+            Some(source) => source,
+            None => return,
+        };
+
+        let mut wakers = Vec::new();
+        let mut waiters = src.waiters();
+        let ready = src.readiness().ready;
+        let removed = waiters.remove_waiter(dir, wake, waiter, ready, &mut wakers);
+
+        drop(waiters);
+        drop(removed);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Whether any waiter is registered in the source of `token`.
+    #[cfg(all(test, feature = "net"))]
+    pub(crate) fn has_waiters(token: Token) -> bool {
+        Reactor::of(token)
+            .sources
+            .get(token)
+            .is_some_and(|src| !src.waiters().is_empty())
+    }
+
+    /// Clears readiness previously returned by `Reactor::poll_ready`,
+    /// once the I/O operation returned `WouldBlock`.
+    pub fn clear_readiness(token: Token, event: ReadyEvent) {
+        if let Some(src) = Reactor::of(token).sources.get(token) {
+            src.clear_readiness(event)
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "rt-drive-io")))]
+mod tests {
+    use super::{Direction, Placement, PollThreadGuard, Reactor, Reactors, WORKER};
+    use crate::io::iosource::Wake;
+    use crate::io::sources::{reactor_of, Sources};
+    use futures::task::{self, ArcWake};
+    use std::io::{Error as IoError, ErrorKind};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, OnceLock};
+
+    /// Waker counting how many times it was woken.
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs a stand-in polling thread over a source with a pending read, which exits
+    /// with `error` or panics if there is none. Returns the wakeups and the failure.
+    fn poll_thread_dies(error: Option<IoError>) -> (usize, IoError) {
+        let sources = Arc::new(Sources::new(0));
+        let failure = Arc::new(OnceLock::new());
+        let token = sources.insert(|_| Ok(())).unwrap();
+
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = task::waker(Arc::clone(&counter));
+        let mut waiter = None;
+        sources.get(token).unwrap().waiters().add_waiter(
+            Direction::Read,
+            &waker,
+            Wake::All,
+            &mut waiter,
+        );
+
+        let guard = PollThreadGuard {
+            sources: Arc::clone(&sources),
+            failure: Arc::clone(&failure),
+            error,
+        };
+        let panics = guard.error.is_none();
+        let thread = std::thread::spawn(move || {
+            let _guard = guard;
+            assert!(!panics, "polling thread died");
+        });
+        let _ = thread.join();
+
+        let failure = failure.get().expect("reactor did not fail").to_error();
+        (counter.0.load(Ordering::Relaxed), failure)
+    }
+
+    #[test]
+    fn poll_error_fails_pending_io() {
+        let (wakes, failure) = poll_thread_dies(Some(IoError::other("bad poll")));
+        assert_eq!(wakes, 1);
+        assert_eq!(failure.kind(), ErrorKind::Other);
+        assert_eq!(failure.to_string(), "I/O reactor failed: bad poll");
+    }
+
+    #[test]
+    fn panic_fails_pending_io() {
+        let (wakes, failure) = poll_thread_dies(None);
+        assert_eq!(wakes, 1);
+        assert_eq!(
+            failure.to_string(),
+            "I/O reactor failed: polling thread panicked"
+        );
+    }
+
+    /// Two reactors of their own, the global ones are set up once per process.
+    fn reactors(placement: Placement) -> Reactors {
+        Reactors {
+            reactors: (0..2).map(|index| Reactor::with_index(index).0).collect(),
+            placement,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Index of the reactor a new source gets, read back from its token.
+    fn place(reactors: &Reactors) -> usize {
+        let token = reactors.pick().sources.insert(|_| Ok(())).unwrap();
+        reactor_of(token)
+    }
+
+    #[test]
+    fn round_robin_alternates_reactors() {
+        let reactors = reactors(Placement::RoundRobin);
+        let placed: Vec<usize> = (0..4).map(|_| place(&reactors)).collect();
+        assert_eq!(placed, [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn worker_placement_sticks_to_the_worker() {
+        let reactors = reactors(Placement::Worker);
+
+        // Other threads go round-robin.
+        assert_eq!(place(&reactors), 0);
+        assert_eq!(place(&reactors), 1);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                WORKER.with(|worker| worker.set(Some(3)));
+                assert!((0..3).all(|_| place(&reactors) == 1));
+            });
+        });
+        assert_eq!(place(&reactors), 0);
     }
 }
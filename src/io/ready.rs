@@ -0,0 +1,170 @@
+use mio::event::Event;
+use std::fmt;
+use std::ops;
+
+/// Set of readiness flags of an I/O source.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Ready(usize);
+
+impl Ready {
+    /// No readiness.
+    pub const EMPTY: Ready = Ready(0);
+
+    /// Readable readiness.
+    pub const READABLE: Ready = Ready(0b01);
+
+    /// Writable readiness.
+    pub const WRITABLE: Ready = Ready(0b10);
+
+    /// The read half was closed, the peer hung up or shut down its writing half.
+    pub const READ_CLOSED: Ready = Ready(0b100);
+
+    /// The write half was closed, the peer hung up or the socket got an error.
+    pub const WRITE_CLOSED: Ready = Ready(0b1000);
+
+    /// The source got an error.
+    pub const ERROR: Ready = Ready(0b10000);
+
+    /// Priority (out-of-band) data is available.
+    pub const PRIORITY: Ready = Ready(0b100000);
+
+    /// Every readiness flag.
+    pub const ALL: Ready = Ready(0b111111);
+
+    /// Converts a mio event into its readiness flags.
+    pub(crate) fn from_event(event: &Event) -> Ready {
+        let mut ready = Ready::EMPTY;
+
+        if event.is_readable() {
+            ready |= Ready::READABLE;
+        }
+
+        if event.is_writable() {
+            ready |= Ready::WRITABLE;
+        }
+
+        if event.is_read_closed() {
+            ready |= Ready::READ_CLOSED;
+        }
+
+        if event.is_write_closed() {
+            ready |= Ready::WRITE_CLOSED;
+        }
+
+        if event.is_error() {
+            ready |= Ready::ERROR;
+        }
+
+        if event.is_priority() {
+            ready |= Ready::PRIORITY;
+        }
+
+        ready
+    }
+
+    pub(crate) fn from_usize(bits: usize) -> Ready {
+        Ready(bits & Ready::ALL.0)
+    }
+
+    pub(crate) fn as_usize(self) -> usize {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Ready::EMPTY
+    }
+
+    pub fn is_readable(self) -> bool {
+        self.contains(Ready::READABLE)
+    }
+
+    pub fn is_writable(self) -> bool {
+        self.contains(Ready::WRITABLE)
+    }
+
+    pub fn is_read_closed(self) -> bool {
+        self.contains(Ready::READ_CLOSED)
+    }
+
+    pub fn is_write_closed(self) -> bool {
+        self.contains(Ready::WRITE_CLOSED)
+    }
+
+    pub fn is_error(self) -> bool {
+        self.contains(Ready::ERROR)
+    }
+
+    pub fn is_priority(self) -> bool {
+        self.contains(Ready::PRIORITY)
+    }
+
+    /// Checks if every flag of `other` is set in `self`.
+    pub fn contains(self, other: Ready) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks if any flag of `other` is set in `self`.
+    pub fn intersects(self, other: Ready) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl ops::BitOr for Ready {
+    type Output = Ready;
+
+    fn bitor(self, other: Ready) -> Ready {
+        Ready(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for Ready {
+    fn bitor_assign(&mut self, other: Ready) {
+        self.0 |= other.0
+    }
+}
+
+impl ops::BitAnd for Ready {
+    type Output = Ready;
+
+    fn bitand(self, other: Ready) -> Ready {
+        Ready(self.0 & other.0)
+    }
+}
+
+impl ops::Sub for Ready {
+    type Output = Ready;
+
+    fn sub(self, other: Ready) -> Ready {
+        Ready(self.0 & !other.0)
+    }
+}
+
+impl fmt::Debug for Ready {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ready")
+            .field("is_readable", &self.is_readable())
+            .field("is_writable", &self.is_writable())
+            .field("is_read_closed", &self.is_read_closed())
+            .field("is_write_closed", &self.is_write_closed())
+            .field("is_error", &self.is_error())
+            .field("is_priority", &self.is_priority())
+            .finish()
+    }
+}
+
+/// Readiness observed on a source, along with the tick it was observed at.
+///
+/// The tick is used by `Reactor::clear_readiness` to not clear
+/// readiness the reactor set after this was observed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadyEvent {
+    pub(crate) ready: Ready,
+    pub(crate) tick: u8,
+}
+
+impl ReadyEvent {
+    /// Readiness flags of the event.
+    pub fn ready(&self) -> Ready {
+        self.ready
+    }
+}
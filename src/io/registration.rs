@@ -0,0 +1,45 @@
+use crate::io::Reactor;
+use mio::event::Source;
+use mio::{Interest, Token};
+use std::io::Result as IoResult;
+
+/// Registration of an I/O source in the reactor, owned by the I/O type wrapping the source.
+///
+/// Holds the token the reactor actually assigned,
+/// and makes the reactor forget about the source once dropped.
+#[derive(Debug)]
+pub struct Registration {
+    token: Token,
+}
+
+impl Registration {
+    /// Registers `src` in the reactor.
+    pub fn new(src: &mut impl Source, interest: Interest) -> IoResult<Registration> {
+        let token = Reactor::register(src, interest)?;
+        Ok(Registration { token })
+    }
+
+    /// Obtains the token the source was registered with.
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    /// Changes the interest of `src`, which has to be the registered source.
+    pub fn reregister(&self, src: &mut impl Source, interest: Interest) -> IoResult<()> {
+        Reactor::reregister(src, self.token, interest)
+    }
+
+    /// Deregisters `src`, which has to be the registered source, from the reactor.
+    ///
+    /// Owners call this before dropping the source, a source that was duplicated
+    /// would stay in the poll otherwise.
+    pub fn deregister(&self, src: &mut impl Source) -> IoResult<()> {
+        Reactor::deregister(src, self.token)
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        Reactor::forget(self.token)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::io::IoSource;
+use mio::Token;
+use slab::Slab;
+use std::io::Result as IoResult;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Tokens handed to mio carry the shard of the source in their lowest bits,
+// its slot in the shard's `Slab` above them, then the reactor it belongs to,
+// and the generation of the source in the rest,
+// so a late event for a slot that got reused does not reach the new source.
+//
+// A slot has to be reused `2^generation bits` times before a late event could reach
+// the wrong source. That is 2^36 on 64-bit targets, but would only be 16 on 32-bit ones,
+// so those trade slots for generations: 4095 sources per shard, and 2^12 generations.
+const SHARD_BITS: u32 = 4;
+const SHARD_MASK: usize = (1 << SHARD_BITS) - 1;
+#[cfg(target_pointer_width = "64")]
+const SLOT_BITS: u32 = 20;
+#[cfg(not(target_pointer_width = "64"))]
+const SLOT_BITS: u32 = 12;
+const SLOT_MASK: usize = (1 << SLOT_BITS) - 1;
+const REACTOR_SHIFT: u32 = SHARD_BITS + SLOT_BITS;
+const REACTOR_BITS: u32 = 4;
+const REACTOR_MASK: usize = (1 << REACTOR_BITS) - 1;
+const GENERATION_SHIFT: u32 = REACTOR_SHIFT + REACTOR_BITS;
+const GENERATION_MASK: usize = usize::MAX >> GENERATION_SHIFT;
+const _: () = assert!(GENERATION_MASK >= (1 << 12) - 1, "too few generation bits");
+
+/// Most reactors there can be, their index has to fit in a token.
+pub(crate) const MAX_REACTORS: usize = 1 << REACTOR_BITS;
+
+/// Amount of shards, sources in different shards never contend on a lock.
+const SHARDS: usize = 1 << SHARD_BITS;
+
+/// Token of the `mio::Waker` interrupting the poll.
+///
+/// It falls into the last slot of a shard, which is never handed out to a source.
+pub(crate) const WAKE_TOKEN: Token = Token(usize::MAX);
+
+fn pack_token(reactor: usize, shard: usize, slot: usize, generation: usize) -> Token {
+    Token(
+        (generation << GENERATION_SHIFT)
+            | (reactor << REACTOR_SHIFT)
+            | (slot << SHARD_BITS)
+            | shard,
+    )
+}
+
+fn unpack_token(token: Token) -> (usize, usize, usize) {
+    (
+        token.0 & SHARD_MASK,
+        (token.0 >> SHARD_BITS) & SLOT_MASK,
+        token.0 >> GENERATION_SHIFT,
+    )
+}
+
+/// Index of the reactor `token` was handed out by.
+pub(crate) fn reactor_of(token: Token) -> usize {
+    (token.0 >> REACTOR_SHIFT) & REACTOR_MASK
+}
+
+/// Sources registered in the reactor, looked up by their token.
+///
+/// The table is split into shards with a lock each, which is only held
+/// to insert, remove, or clone out a source. Everything else happens on the
+/// `IoSource` itself, so sockets do not wait on each other.
+pub(crate) struct Sources {
+    shards: Box<[Mutex<Slab<Arc<IoSource>>>]>,
+
+    /// Index of the reactor owning the table.
+    reactor: usize,
+
+    /// Generation given to the next inserted source, it also picks its shard.
+    generation: AtomicUsize,
+}
+
+impl Sources {
+    pub(crate) fn new(reactor: usize) -> Sources {
+        Sources {
+            shards: (0..SHARDS)
+                .map(|_| Mutex::new(Slab::with_capacity(64)))
+                .collect(),
+            reactor,
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    /// Inserts a new source, handing out its token.
+    ///
+    /// `register` is called with the token first, the source is only inserted if it succeeds.
+    pub(crate) fn insert(&self, register: impl FnOnce(Token) -> IoResult<()>) -> IoResult<Token> {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed);
+        let shard = generation & SHARD_MASK;
+        let generation = (generation >> SHARD_BITS) & GENERATION_MASK;
+
+        let mut sources = self.shards[shard].lock().expect("failed source lock");
+        let slot = sources.vacant_key();
+        if slot >= SLOT_MASK {
+            return Err(std::io::Error::other(
+                "too many sources registered in the reactor",
+            ));
+        }
+
+        let token = pack_token(self.reactor, shard, slot, generation);
+        register(token)?;
+
+        let _key = sources.insert(Arc::new(IoSource::new(generation)));
+        Ok(token)
+    }
+
+    /// Looks up the source of `token`, if its generation still matches.
+    pub(crate) fn get(&self, token: Token) -> Option<Arc<IoSource>> {
+        let (shard, slot, generation) = unpack_token(token);
+        let sources = self.shards[shard].lock().expect("failed source lock");
+        sources
+            .get(slot)
+            .filter(|src| src.generation() == generation)
+            .cloned()
+    }
+
+    /// Removes the source of `token`, if its generation still matches.
+    pub(crate) fn remove(&self, token: Token) -> Option<Arc<IoSource>> {
+        let (shard, slot, generation) = unpack_token(token);
+        let mut sources = self.shards[shard].lock().expect("failed source lock");
+        match sources.get(slot) {
+            Some(src) if src.generation() == generation => Some(sources.remove(slot)),
+            _ => None,
+        }
+    }
+
+    /// Clones out every source.
+    ///
+    /// A panic while holding a shard lock poisons it, the sources are still fine to use.
+    pub(crate) fn all(&self) -> Vec<Arc<IoSource>> {
+        let mut all = Vec::new();
+        for shard in self.shards.iter() {
+            let sources = match shard.lock() {
+                Ok(sources) => sources,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            all.extend(sources.iter().map(|(_, src)| Arc::clone(src)));
+        }
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack_token, reactor_of, unpack_token, Sources, GENERATION_MASK, SLOT_MASK};
+
+    #[test]
+    fn token_round_trip() {
+        let token = pack_token(3, 5, SLOT_MASK - 1, GENERATION_MASK);
+        assert_eq!(unpack_token(token), (5, SLOT_MASK - 1, GENERATION_MASK));
+        assert_eq!(reactor_of(token), 3);
+    }
+
+    #[test]
+    fn stale_token_misses_reused_slot() {
+        let sources = Sources::new(0);
+        let stale = sources.insert(|_| Ok(())).unwrap();
+        assert!(sources.remove(stale).is_some());
+
+        // Go around every shard, so the next source lands in the freed slot.
+        let tokens: Vec<_> = (0..16)
+            .map(|_| sources.insert(|_| Ok(())).unwrap())
+            .collect();
+        let reused = tokens
+            .iter()
+            .find(|token| unpack_token(**token).0 == unpack_token(stale).0)
+            .unwrap();
+        assert_eq!(unpack_token(*reused).1, unpack_token(stale).1);
+
+        assert!(sources.get(stale).is_none());
+        assert!(sources.remove(stale).is_none());
+        assert!(sources.get(*reused).is_some());
+    }
+}
@@ -0,0 +1,21 @@
+//! Horrible attempt at some sort of an async runtime, built on top of mio.
+//!
+//! Most of the crate is behind cargo features, see the `[features]` table of the manifest.
+
+/// Asynchronous I/O traits and the I/O Reactor driving them.
+pub mod io;
+
+/// Networking types.
+#[cfg(feature = "net")]
+pub mod net;
+
+/// The async runtime.
+#[cfg(feature = "rt")]
+pub mod runtime;
+
+/// Asynchronous synchronization primitives.
+#[cfg(feature = "sync")]
+pub mod sync;
+
+#[cfg(feature = "macros")]
+pub use apple_macros::{main, test};
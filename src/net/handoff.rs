@@ -0,0 +1,116 @@
+// crate imports
+use crate::net::scm::MAX_FDS;
+use crate::net::{TcpListener, UnixStream};
+
+/// std imports
+use std::collections::BTreeSet;
+use std::io;
+use std::os::unix::io::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::Mutex;
+
+/// File descriptors of the `TcpListener`s alive in the process.
+static LISTENERS: Mutex<BTreeSet<RawFd>> = Mutex::new(BTreeSet::new());
+
+pub(crate) fn track(fd: RawFd) {
+    LISTENERS.lock().expect("failed listeners lock").insert(fd);
+}
+
+pub(crate) fn untrack(fd: RawFd) {
+    LISTENERS.lock().expect("failed listeners lock").remove(&fd);
+}
+
+/// Hands every `TcpListener` alive in the process to the process on the other end of `to`,
+/// which takes them with `adopt_listeners`. Returns the amount of listeners handed off.
+///
+/// The listeners keep working here too, both processes accept connections
+/// until this one drops its listeners. Copies of the descriptors are sent,
+/// so listeners dropped while this runs are still handed off.
+pub async fn hand_off_listeners(to: &UnixStream) -> io::Result<usize> {
+    let fds = LISTENERS
+        .lock()
+        .expect("failed listeners lock")
+        .iter()
+        .map(|&fd| {
+            // Safety: listeners untrack their fd before closing it, which can't happen
+            // while we hold the lock.
+            unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()
+        })
+        .collect::<io::Result<Vec<OwnedFd>>>()?;
+
+    // Every message carries one byte telling if more follow, and at least one is sent.
+    let mut chunks = fds.chunks(MAX_FDS).peekable();
+    loop {
+        let chunk: Vec<RawFd> = chunks
+            .next()
+            .unwrap_or(&[])
+            .iter()
+            .map(AsRawFd::as_raw_fd)
+            .collect();
+        let more = chunks.peek().is_some();
+        to.send_with_fds(&[more as u8], &chunk).await?;
+
+        if !more {
+            return Ok(fds.len());
+        }
+    }
+}
+
+/// Takes the listeners the process on the other end of `from` hands off
+/// with `hand_off_listeners`, registering them in the reactor.
+pub async fn adopt_listeners(from: &UnixStream) -> io::Result<Vec<TcpListener>> {
+    let mut listeners = Vec::new();
+    loop {
+        let mut more = [0u8; 1];
+        let (size, fds) = from.recv_with_fds(&mut more, MAX_FDS).await?;
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "hung up before handing off every listener",
+            ));
+        }
+
+        for fd in fds {
+            listeners.push(TcpListener::from_std(std::net::TcpListener::from(fd))?);
+        }
+
+        if more[0] == 0 {
+            return Ok(listeners);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::{adopt_listeners, hand_off_listeners};
+    use crate::net::{TcpListener, TcpStream, UnixStream};
+    use crate::runtime::{Flavor, Runtime};
+    use std::time::Duration;
+
+    #[test]
+    fn listeners_are_adopted_across_a_socketpair() {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        let future = async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (to, from) = UnixStream::pair().unwrap();
+
+            // Listeners of other tests may be handed off along with ours.
+            let handed_off = hand_off_listeners(&to).await.unwrap();
+            let adopted = adopt_listeners(&from).await.unwrap();
+            assert_eq!(adopted.len(), handed_off);
+
+            let adopted = adopted
+                .into_iter()
+                .find(|adopted| adopted.local_addr().unwrap() == addr)
+                .expect("listener was not handed off");
+
+            // Both share the socket, so the adopted one accepts connections to it.
+            let client = TcpStream::connect(addr).await.unwrap();
+            let (_, peer) = adopted.accept().await.unwrap();
+            assert_eq!(peer, client.local_addr().unwrap());
+        };
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+}
@@ -0,0 +1,104 @@
+/// Implements `AsyncRead` and `AsyncWrite` for a stream and for references to it,
+/// on top of its `io: PollEvented` field.
+///
+/// Writes go straight to the socket, so there is nothing to flush,
+/// and shutting down only shuts down the writing direction.
+macro_rules! impl_async_stream {
+    ($stream:ty) => {
+        impl_async_stream!(@impl $stream);
+        impl_async_stream!(@impl &$stream);
+    };
+    (@impl $stream:ty) => {
+        impl $crate::io::AsyncRead for $stream {
+            fn poll_read(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &mut [u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.io.poll_read(cx, buf)
+            }
+        }
+
+        impl $crate::io::AsyncWrite for $stream {
+            fn poll_write(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.io.poll_write(cx, buf)
+            }
+
+            fn poll_write_vectored(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                bufs: &[std::io::IoSlice<'_>],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.io.poll_write_vectored(cx, bufs)
+            }
+
+            fn poll_flush(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(Ok(()))
+            }
+
+            fn poll_shutdown(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(self.io.get_ref().shutdown(std::net::Shutdown::Write))
+            }
+        }
+    };
+}
+
+/// TcpStream struct.
+mod tcp_stream;
+pub use tcp_stream::{ClosedFuture, ReadyFuture, TcpStream};
+
+/// TcpListener struct.
+mod tcp_listener;
+pub use tcp_listener::{AcceptFuture, Incoming, TcpListener};
+
+/// Halves of a TcpStream.
+mod tcp_split;
+pub use tcp_split::{OwnedReadHalf, OwnedWriteHalf, ReadHalf, ReuniteError, WriteHalf};
+
+/// UdpSocket struct.
+mod udp_socket;
+pub use udp_socket::{RecvFromFuture, RecvFuture, SendFuture, UdpSocket};
+
+/// Credentials of the peer of Unix sockets.
+#[cfg(unix)]
+mod ucred;
+#[cfg(unix)]
+pub use ucred::UCred;
+
+/// UnixStream struct.
+#[cfg(unix)]
+mod unix_stream;
+#[cfg(unix)]
+pub use unix_stream::UnixStream;
+
+/// UnixListener struct.
+#[cfg(unix)]
+mod unix_listener;
+#[cfg(unix)]
+pub use unix_listener::UnixListener;
+
+/// UnixDatagram struct.
+#[cfg(unix)]
+mod unix_datagram;
+#[cfg(unix)]
+pub use unix_datagram::UnixDatagram;
+
+/// File descriptor passing over Unix sockets.
+#[cfg(unix)]
+mod scm;
+
+/// Handing listeners off to a successor process.
+#[cfg(unix)]
+mod handoff;
+#[cfg(unix)]
+pub use handoff::{adopt_listeners, hand_off_listeners};
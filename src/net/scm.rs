@@ -0,0 +1,196 @@
+use std::io;
+use std::mem;
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::ptr;
+
+/// Most file descriptors the kernel takes in one message, `SCM_MAX_FD` on Linux.
+pub(crate) const MAX_FDS: usize = 253;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const SEND_FLAGS: libc::c_int = 0;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: libc::c_int = 0;
+
+/// Buffer for the ancillary data of `fds` file descriptors, aligned for `cmsghdr`.
+fn cmsg_buffer(fds: usize) -> (Vec<u64>, usize) {
+    // Safety: `CMSG_SPACE` only computes a size.
+    let space = unsafe { libc::CMSG_SPACE((fds * mem::size_of::<RawFd>()) as u32) } as usize;
+    (vec![0; space.div_ceil(mem::size_of::<u64>())], space)
+}
+
+/// Sends `buf` over the Unix socket `sock`, along with `fds` as `SCM_RIGHTS` ancillary data.
+///
+/// `buf` can't be empty, stream sockets drop the file descriptors of empty messages.
+pub(crate) fn send_with_fds(sock: RawFd, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    if buf.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "file descriptors have to be sent along with data",
+        ));
+    }
+    if fds.len() > MAX_FDS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "too many file descriptors for one message",
+        ));
+    }
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let (mut cmsg_buf, space) = cmsg_buffer(fds.len());
+
+    // Safety: an all zero `msghdr` is valid, and the pointers set below outlive the `sendmsg`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    if !fds.is_empty() {
+        msg.msg_control = cmsg_buf.as_mut_ptr().cast();
+        msg.msg_controllen = space as _;
+
+        // Safety: the control buffer has room for one header followed by `fds`.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of_val(fds) as u32) as _;
+            ptr::copy_nonoverlapping(
+                fds.as_ptr(),
+                libc::CMSG_DATA(cmsg).cast::<RawFd>(),
+                fds.len(),
+            );
+        }
+    }
+
+    // Safety: `msg` is fully set up.
+    let sent = unsafe { libc::sendmsg(sock, &msg, SEND_FLAGS) };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(sent as usize)
+}
+
+/// Receives into `buf` from the Unix socket `sock`, along with up to `max_fds`
+/// file descriptors sent as `SCM_RIGHTS` ancillary data.
+///
+/// Fails if more file descriptors came than fit, those are closed.
+pub(crate) fn recv_with_fds(
+    sock: RawFd,
+    buf: &mut [u8],
+    max_fds: usize,
+) -> io::Result<(usize, Vec<OwnedFd>)> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    let (mut cmsg_buf, space) = cmsg_buffer(max_fds);
+
+    // Safety: an all zero `msghdr` is valid, and the pointers set below outlive the `recvmsg`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if max_fds > 0 {
+        msg.msg_control = cmsg_buf.as_mut_ptr().cast();
+        msg.msg_controllen = space as _;
+    }
+
+    // Safety: `msg` is fully set up.
+    let received = unsafe { libc::recvmsg(sock, &mut msg, RECV_FLAGS) };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut fds = Vec::new();
+
+    // Safety: the kernel filled the control buffer with well formed headers,
+    // and the file descriptors in them are ours now.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                let len = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize)
+                    / mem::size_of::<RawFd>();
+                for i in 0..len {
+                    let fd = ptr::read_unaligned(data.add(i));
+                    fds.push(OwnedFd::from_raw_fd(fd));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    for fd in &fds {
+        use std::os::unix::io::AsRawFd;
+        // Safety: `fd` is open and ours.
+        unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(io::Error::other(
+            "more file descriptors were sent than could be received",
+        ));
+    }
+    Ok((received as usize, fds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recv_with_fds, send_with_fds};
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn fds_round_trip() {
+        let (tx, rx) = UnixStream::pair().unwrap();
+        let (sent, mut peer) = UnixStream::pair().unwrap();
+
+        assert_eq!(
+            send_with_fds(tx.as_raw_fd(), b"x", &[sent.as_raw_fd()]).unwrap(),
+            1
+        );
+        let mut buf = [0u8; 4];
+        let (read, mut fds) = recv_with_fds(rx.as_raw_fd(), &mut buf, 4).unwrap();
+        assert_eq!(&buf[..read], b"x");
+        assert_eq!(fds.len(), 1);
+
+        // The received descriptor is the same socket as the one sent.
+        drop(sent);
+        let mut received = UnixStream::from(fds.remove(0));
+        received.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+    }
+
+    #[test]
+    fn too_many_fds_fail() {
+        let (tx, rx) = UnixStream::pair().unwrap();
+        let (a, b) = UnixStream::pair().unwrap();
+        let fds = [a.as_raw_fd(), b.as_raw_fd(), a.as_raw_fd(), b.as_raw_fd()];
+        send_with_fds(tx.as_raw_fd(), b"x", &fds).unwrap();
+
+        let mut buf = [0u8; 4];
+        let e = recv_with_fds(rx.as_raw_fd(), &mut buf, 1).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "more file descriptors were sent than could be received"
+        );
+    }
+
+    #[test]
+    fn empty_message_is_rejected() {
+        let (tx, _rx) = UnixStream::pair().unwrap();
+        let e = send_with_fds(tx.as_raw_fd(), b"", &[tx.as_raw_fd()]).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
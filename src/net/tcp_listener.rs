@@ -0,0 +1,256 @@
+// crate imports
+use crate::io::reactor::Direction;
+use crate::io::{Interest, PollEvented, WaiterId};
+#[cfg(unix)]
+use crate::net::handoff;
+use crate::net::TcpStream;
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use futures::Stream;
+use std::future::Future;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Future representing the operation of accepting a connection on a `TcpListener`.
+///
+/// Cancel safe: connections are only accepted in the poll that returns them,
+/// so dropping the future before it completes never loses one.
+/// A connection wakes every task accepting, the first one to poll gets it.
+pub struct AcceptFuture<'o> {
+    listener: &'o TcpListener,
+    waiter: Option<WaiterId>,
+}
+
+impl Future for AcceptFuture<'_> {
+    type Output = io::Result<(TcpStream, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        future.listener.poll_accept(cx, &mut future.waiter)
+    }
+}
+
+impl Drop for AcceptFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.listener.io.remove_waiter(Direction::Read, waiter);
+        }
+    }
+}
+
+/// Stream of the connections accepted by a `TcpListener`, see `TcpListener::incoming`.
+///
+/// Never ends, errors of single accepts are yielded as they come.
+pub struct Incoming<'o> {
+    listener: &'o TcpListener,
+    waiter: Option<WaiterId>,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<TcpStream>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let stream = self.get_mut();
+        let accepted = ready!(stream.listener.poll_accept(cx, &mut stream.waiter));
+        Poll::Ready(Some(accepted.map(|(stream, _)| stream)))
+    }
+}
+
+impl Drop for Incoming<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.listener.io.remove_waiter(Direction::Read, waiter);
+        }
+    }
+}
+
+/// TCP socket listening for connections.
+pub struct TcpListener {
+    io: PollEvented<net::TcpListener>,
+}
+
+impl TcpListener {
+    /// Binds a listener to the first address of `addr` that works,
+    /// registering it in the reactor.
+    ///
+    /// Resolving `addr` may block, like `std::net::TcpListener::bind` does.
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<TcpListener> {
+        let mut last_err = None;
+
+        for addr in addr.to_socket_addrs()? {
+            match net::TcpListener::bind(addr) {
+                Ok(io) => return TcpListener::from_mio(io),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Wraps a listener from the standard library, registering it in the reactor.
+    ///
+    /// The listener is switched to non-blocking mode.
+    pub fn from_std(listener: std::net::TcpListener) -> io::Result<TcpListener> {
+        listener.set_nonblocking(true)?;
+        TcpListener::from_mio(net::TcpListener::from_std(listener))
+    }
+
+    fn from_mio(io: net::TcpListener) -> io::Result<TcpListener> {
+        let io = PollEvented::with_interest(io, Interest::READABLE)?;
+        #[cfg(unix)]
+        handoff::track(io.get_ref().as_raw_fd());
+        Ok(TcpListener { io })
+    }
+
+    /// Accepts a new connection, along with the address of the peer.
+    ///
+    /// The accepted `TcpStream` is registered in the reactor already.
+    pub fn accept(&self) -> AcceptFuture<'_> {
+        AcceptFuture {
+            listener: self,
+            waiter: None,
+        }
+    }
+
+    /// Stream of the accepted connections.
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            listener: self,
+            waiter: None,
+        }
+    }
+
+    /// Obtains the address the listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Accepts a connection if one is pending, registering `waiter` otherwise.
+    ///
+    /// Every task accepting is woken by a connection, like for `UnixListener::accept`,
+    /// so a cancelled accept can never swallow the wakeup of another one.
+    fn poll_accept(
+        &self,
+        cx: &mut Context<'_>,
+        waiter: &mut Option<WaiterId>,
+    ) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        let (io, addr) = ready!(self
+            .io
+            .poll_io(cx, Direction::Read, waiter, |io| io.accept()))?;
+        Poll::Ready(TcpStream::from_mio(io).map(|stream| (stream, addr)))
+    }
+}
+
+#[cfg(unix)]
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl TryFrom<std::net::TcpListener> for TcpListener {
+    type Error = io::Error;
+
+    /// Same as `TcpListener::from_std`.
+    fn try_from(listener: std::net::TcpListener) -> io::Result<TcpListener> {
+        TcpListener::from_std(listener)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TcpListener {
+    /// Stops handing the listener off, before `io` closes its descriptor.
+    fn drop(&mut self) {
+        handoff::untrack(self.io.get_ref().as_raw_fd());
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::TcpListener;
+    use crate::net::TcpStream;
+    use crate::runtime::{Flavor, Runtime};
+    use futures::StreamExt;
+    use std::future::{poll_fn, Future};
+    use std::pin::Pin;
+    use std::task::Poll;
+    use std::time::Duration;
+
+    fn run<F: Future<Output = ()> + Send + 'static>(future: F) {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    #[test]
+    fn accept_returns_the_peer() {
+        run(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+
+            let (accepted, peer) = listener.accept().await.unwrap();
+            assert_eq!(peer, client.local_addr().unwrap());
+            assert_eq!(accepted.peer_addr().unwrap(), peer);
+        });
+    }
+
+    #[test]
+    fn incoming_yields_every_connection() {
+        run(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let a = TcpStream::connect(addr).await.unwrap();
+            let b = TcpStream::connect(addr).await.unwrap();
+
+            let mut peers: Vec<_> = listener
+                .incoming()
+                .take(2)
+                .map(|stream| stream.unwrap().peer_addr().unwrap())
+                .collect()
+                .await;
+            let mut expected = vec![a.local_addr().unwrap(), b.local_addr().unwrap()];
+            peers.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(peers, expected);
+        });
+    }
+
+    #[test]
+    fn cancelled_accept_does_not_take_the_connection() {
+        run(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut first = listener.accept();
+            let mut second = listener.accept();
+
+            // Both wait on the listener before anything connects.
+            poll_fn(|cx| {
+                assert!(Pin::new(&mut first).poll(cx).is_pending());
+                assert!(Pin::new(&mut second).poll(cx).is_pending());
+                Poll::Ready(())
+            })
+            .await;
+            drop(first);
+
+            let client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (_, peer) = second.await.unwrap();
+            assert_eq!(peer, client.local_addr().unwrap());
+        });
+    }
+}
@@ -0,0 +1,297 @@
+// crate imports
+use crate::io::{AsyncRead, AsyncWrite};
+use crate::net::TcpStream;
+
+/// std imports
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IoSlice};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Reading half of a `TcpStream`, borrowed by `TcpStream::split`.
+#[derive(Debug)]
+pub struct ReadHalf<'o>(&'o TcpStream);
+
+/// Writing half of a `TcpStream`, borrowed by `TcpStream::split`.
+#[derive(Debug)]
+pub struct WriteHalf<'o>(&'o TcpStream);
+
+/// Reading half of a `TcpStream`, owned through `TcpStream::into_split`.
+#[derive(Debug)]
+pub struct OwnedReadHalf {
+    inner: Arc<TcpStream>,
+}
+
+/// Writing half of a `TcpStream`, owned through `TcpStream::into_split`.
+///
+/// Shuts down the writing direction of the stream once dropped, so the peer sees the end.
+#[derive(Debug)]
+pub struct OwnedWriteHalf {
+    inner: Arc<TcpStream>,
+    shutdown_on_drop: bool,
+}
+
+/// Halves passed to `OwnedReadHalf::reunite` that were not split from the same stream.
+#[derive(Debug)]
+pub struct ReuniteError(pub OwnedReadHalf, pub OwnedWriteHalf);
+
+impl fmt::Display for ReuniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tried to reunite halves that are not from the same stream"
+        )
+    }
+}
+
+impl Error for ReuniteError {}
+
+impl TcpStream {
+    /// Splits the stream into a reading and a writing half borrowing it,
+    /// which can be used at the same time.
+    pub fn split(&mut self) -> (ReadHalf<'_>, WriteHalf<'_>) {
+        (ReadHalf(self), WriteHalf(self))
+    }
+
+    /// Splits the stream into a reading and a writing half that can be moved into different tasks.
+    ///
+    /// `OwnedReadHalf::reunite` puts them back together.
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        let inner = Arc::new(self);
+        (
+            OwnedReadHalf {
+                inner: Arc::clone(&inner),
+            },
+            OwnedWriteHalf {
+                inner,
+                shutdown_on_drop: true,
+            },
+        )
+    }
+}
+
+impl OwnedReadHalf {
+    /// Puts the halves back together into the `TcpStream` they were split from.
+    ///
+    /// Hands the halves back if they are from different streams.
+    pub fn reunite(self, other: OwnedWriteHalf) -> Result<TcpStream, ReuniteError> {
+        reunite(self, other)
+    }
+
+    /// Obtains the address of the peer of the stream.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    /// Obtains the local address of the stream.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+impl OwnedWriteHalf {
+    /// Puts the halves back together, see `OwnedReadHalf::reunite`.
+    pub fn reunite(self, other: OwnedReadHalf) -> Result<TcpStream, ReuniteError> {
+        reunite(other, self)
+    }
+
+    /// Obtains the address of the peer of the stream.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    /// Obtains the local address of the stream.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+fn reunite(read: OwnedReadHalf, mut write: OwnedWriteHalf) -> Result<TcpStream, ReuniteError> {
+    if !Arc::ptr_eq(&read.inner, &write.inner) {
+        return Err(ReuniteError(read, write));
+    }
+
+    write.shutdown_on_drop = false;
+    drop(write);
+
+    let stream =
+        Arc::try_unwrap(read.inner).expect("TcpStream halves are the only owners of the stream");
+    Ok(stream)
+}
+
+impl Drop for OwnedWriteHalf {
+    fn drop(&mut self) {
+        if self.shutdown_on_drop {
+            let _ = self.inner.shutdown_write();
+        }
+    }
+}
+
+impl AsyncRead for ReadHalf<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for WriteHalf<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Shuts down the writing direction of the stream, the reading half keeps working.
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.shutdown_write())
+    }
+}
+
+impl AsyncRead for OwnedReadHalf {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for OwnedWriteHalf {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Shuts down the writing direction of the stream, the reading half keeps working.
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let half = self.get_mut();
+        half.shutdown_on_drop = false;
+        Poll::Ready(half.inner.shutdown_write())
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use crate::io::{AsyncReadExt, AsyncWriteExt};
+    use crate::net::{TcpListener, TcpStream};
+    use crate::runtime::{Flavor, Runtime};
+    use std::future::Future;
+    use std::time::Duration;
+
+    fn run<F: Future<Output = ()> + Send + 'static>(future: F) {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    /// Both ends of a loopback connection.
+    async fn connected() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn borrowed_halves() {
+        run(async {
+            let (mut client, mut server) = connected().await;
+            let (mut read, mut write) = client.split();
+
+            write.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            server.read_exact(&mut buf).await.unwrap();
+            server.write_all(b"pong").await.unwrap();
+            read.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"pong");
+        });
+    }
+
+    #[test]
+    fn owned_halves_reunite() {
+        run(async {
+            let (client, mut server) = connected().await;
+            let addr = client.local_addr().unwrap();
+            let (read, write) = client.into_split();
+
+            let mut client = read.reunite(write).unwrap();
+            assert_eq!(client.local_addr().unwrap(), addr);
+
+            // Reuniting does not shut the stream down.
+            client.write_all(b"still").await.unwrap();
+            let mut buf = [0u8; 5];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"still");
+        });
+    }
+
+    #[test]
+    fn mismatched_halves_are_handed_back() {
+        run(async {
+            let (a, b) = connected().await;
+            let (a_read, a_write) = a.into_split();
+            let (b_read, b_write) = b.into_split();
+
+            let e = a_read.reunite(b_write).unwrap_err();
+            assert!(e.0.reunite(a_write).is_ok());
+            assert!(b_read.reunite(e.1).is_ok());
+        });
+    }
+
+    #[test]
+    fn dropping_the_write_half_ends_the_stream() {
+        run(async {
+            let (client, mut server) = connected().await;
+            let (mut read, write) = client.into_split();
+            drop(write);
+
+            let mut buf = Vec::new();
+            assert_eq!(server.read_to_end(&mut buf).await.unwrap(), 0);
+
+            // The reading half keeps working.
+            server.write_all(b"late").await.unwrap();
+            let mut buf = [0u8; 4];
+            read.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"late");
+        });
+    }
+}
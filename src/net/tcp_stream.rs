@@ -0,0 +1,316 @@
+// crate imports
+use crate::io::reactor::Direction;
+use crate::io::Reactor;
+use crate::io::{Interest, PollEvented, Ready, WaiterId, Wake};
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use std::future::{poll_fn, Future};
+use std::io;
+use std::net::{Shutdown, SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Future waiting for a `TcpStream` to become ready for any of an `Interest`.
+///
+/// Resolves to the readiness that was found, which includes
+/// closing and errors of the directions the interest covers.
+pub struct ReadyFuture<'o> {
+    io: &'o PollEvented<net::TcpStream>,
+    waiters: Vec<(Direction, Option<WaiterId>)>,
+}
+
+impl Future for ReadyFuture<'_> {
+    type Output = io::Result<Ready>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let mut ready = Ready::EMPTY;
+
+        for (dir, waiter) in future.waiters.iter_mut() {
+            if let Poll::Ready(event) = future.io.poll_ready(cx, *dir, waiter) {
+                ready |= event?.ready();
+            }
+        }
+
+        if ready.is_empty() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(ready))
+        }
+    }
+}
+
+impl Drop for ReadyFuture<'_> {
+    fn drop(&mut self) {
+        for (dir, waiter) in self.waiters.iter_mut() {
+            if let Some(waiter) = waiter.take() {
+                self.io.remove_waiter(*dir, waiter);
+            }
+        }
+    }
+}
+
+/// Future waiting for the peer of a `TcpStream` to hang up,
+/// or for the stream to get an error.
+pub struct ClosedFuture<'o> {
+    io: &'o PollEvented<net::TcpStream>,
+    waiter: Option<WaiterId>,
+}
+
+impl Future for ClosedFuture<'_> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let mask = Ready::READ_CLOSED | Ready::WRITE_CLOSED | Ready::ERROR;
+
+        // Hang-ups always come with read readiness, so the read waiters get woken for them.
+        let _ = ready!(Reactor::poll_ready_mask(
+            cx,
+            future.io.registration().token(),
+            Direction::Read,
+            mask,
+            Wake::All,
+            &mut future.waiter
+        ))?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for ClosedFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.io.remove_waiter(Direction::Read, waiter);
+        }
+    }
+}
+
+/// TCP Socket connected to a listener.
+pub struct TcpStream {
+    io: PollEvented<net::TcpStream>,
+}
+
+impl std::fmt::Debug for TcpStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpStream")
+            .field("io", self.io.get_ref())
+            .field("token", &self.io.registration().token())
+            .finish()
+    }
+}
+
+/// Impl TcpStream
+impl TcpStream {
+    /// Create a new TcpStream, registered in the reactor.
+    ///
+    /// The connection is only started, `_tkn` is not used anymore,
+    /// the stream keeps the token the reactor assigned.
+    #[deprecated(note = "use `TcpStream::connect`, which waits for the connection")]
+    pub fn new(addr: &str, _tkn: usize) -> io::Result<TcpStream> {
+        let address = match addr.parse() {
+            Ok(o) => o,
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+        };
+
+        TcpStream::from_mio(net::TcpStream::connect(address)?)
+    }
+}
+
+impl TcpStream {
+    /// Opens a connection to the first address of `addr` that accepts it,
+    /// registering the stream in the reactor.
+    ///
+    /// Resolving `addr` may block, like `std::net::TcpStream::connect` does.
+    /// Fails with the error of the last address if none of them worked.
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<TcpStream> {
+        let mut last_err = None;
+
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_addr(addr).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Opens a connection to `addr`, waiting until it is established.
+    async fn connect_addr(addr: SocketAddr) -> io::Result<TcpStream> {
+        let stream = TcpStream::from_mio(net::TcpStream::connect(addr)?)?;
+
+        // Dropping the stream deregisters it, which takes the waiter along.
+        let mut waiter = None;
+        loop {
+            // The connect finished one way or another once the socket is writable.
+            let event =
+                poll_fn(|cx| stream.io.poll_ready(cx, Direction::Write, &mut waiter)).await?;
+
+            if let Some(e) = stream.io.get_ref().take_error()? {
+                return Err(e);
+            }
+
+            match stream.io.get_ref().peer_addr() {
+                Ok(_) => return Ok(stream),
+                // Still connecting, the wakeup was spurious.
+                Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                    stream.io.clear_readiness(event)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Obtains the address of the peer of the stream.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    /// Obtains the local address of the stream.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Wraps a connected mio stream, registering it in the reactor.
+    pub(crate) fn from_mio(io: net::TcpStream) -> io::Result<TcpStream> {
+        Ok(TcpStream {
+            io: PollEvented::new(io)?,
+        })
+    }
+}
+
+impl TcpStream {
+    /// Waits for the stream to become ready for any of `interest`.
+    ///
+    /// The returned readiness also reports if the peer hung up or the stream got an error.
+    pub fn ready(&self, interest: Interest) -> ReadyFuture<'_> {
+        ReadyFuture {
+            io: &self.io,
+            waiters: Direction::from_interest(interest)
+                .map(|dir| (dir, None))
+                .collect(),
+        }
+    }
+
+    /// Waits for the peer to hang up, or for the stream to get an error.
+    pub fn closed(&self) -> ClosedFuture<'_> {
+        ClosedFuture {
+            io: &self.io,
+            waiter: None,
+        }
+    }
+}
+
+impl TcpStream {
+    /// Shuts down the writing half, nothing is buffered so there is nothing to flush first.
+    pub(crate) fn shutdown_write(&self) -> io::Result<()> {
+        self.io.get_ref().shutdown(Shutdown::Write)
+    }
+}
+
+impl_async_stream!(TcpStream);
+
+impl io::Read for &TcpStream {
+    /// Works by calling the underlying `io`'s `read` function.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut io = self.io.get_ref();
+        io.read(buf)
+    }
+}
+
+impl io::Write for &TcpStream {
+    /// Works by calling the underlying `io`'s `write` function.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut io = self.io.get_ref();
+        io.write(buf)
+    }
+
+    /// Works by calling the underlying `io`'s `flush` function.
+    fn flush(&mut self) -> io::Result<()> {
+        let mut io = self.io.get_ref();
+        io.flush()
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::TcpStream;
+    use crate::io::{AsyncReadExt, AsyncWriteExt, Interest};
+    use crate::net::TcpListener;
+    use crate::runtime::{Flavor, Runtime};
+    use std::future::{poll_fn, Future};
+    use std::io::ErrorKind;
+    use std::pin::pin;
+    use std::task::Poll;
+    use std::time::Duration;
+
+    fn run<F: Future<Output = ()> + Send + 'static>(future: F) {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    #[test]
+    fn connect_to_a_loopback_listener() {
+        run(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (mut server, _) = listener.accept().await.unwrap();
+            assert_eq!(client.peer_addr().unwrap(), listener.local_addr().unwrap());
+
+            client.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+        });
+    }
+
+    #[test]
+    fn closed_resolves_once_the_peer_hangs_up() {
+        run(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (server, _) = listener.accept().await.unwrap();
+
+            let mut closed = pin!(client.closed());
+            poll_fn(|cx| {
+                assert!(closed.as_mut().poll(cx).is_pending());
+                Poll::Ready(())
+            })
+            .await;
+
+            drop(server);
+            closed.await.unwrap();
+
+            let ready = client.ready(Interest::READABLE).await.unwrap();
+            assert!(ready.is_read_closed());
+        });
+    }
+
+    #[test]
+    fn connect_refused() {
+        // Nothing listens on the port once the listener is gone.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        run(async move {
+            let e = TcpStream::connect(addr).await.unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::ConnectionRefused);
+        });
+    }
+}
@@ -0,0 +1,409 @@
+// crate imports
+use crate::io::reactor::Direction;
+use crate::io::{PollEvented, WaiterId};
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use std::future::Future;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future representing the operation of sending a datagram from a `UdpSocket`,
+/// see `UdpSocket::send` and `UdpSocket::send_to`.
+///
+/// Cancel safe: if the future is dropped before it completes, nothing was sent.
+pub struct SendFuture<'o> {
+    io: &'o PollEvented<net::UdpSocket>,
+    buf: &'o [u8],
+    /// `None` to send to the connected peer.
+    target: Option<SocketAddr>,
+    waiter: Option<WaiterId>,
+}
+
+impl Future for SendFuture<'_> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let (buf, target) = (future.buf, future.target);
+        future.io.poll_io(
+            cx,
+            Direction::Write,
+            &mut future.waiter,
+            |io| match target {
+                Some(target) => io.send_to(buf, target),
+                None => io.send(buf),
+            },
+        )
+    }
+}
+
+impl Drop for SendFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.io.remove_waiter(Direction::Write, waiter);
+        }
+    }
+}
+
+/// Future representing the operation of receiving a datagram from the connected peer
+/// of a `UdpSocket`, see `UdpSocket::recv`.
+///
+/// Cancel safe: datagrams are only received in the poll that returns them.
+pub struct RecvFuture<'o> {
+    io: &'o PollEvented<net::UdpSocket>,
+    buf: &'o mut [u8],
+    waiter: Option<WaiterId>,
+}
+
+impl Future for RecvFuture<'_> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let buf = &mut *future.buf;
+        future
+            .io
+            .poll_io(cx, Direction::Read, &mut future.waiter, |io| io.recv(buf))
+    }
+}
+
+impl Drop for RecvFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.io.remove_waiter(Direction::Read, waiter);
+        }
+    }
+}
+
+/// Future representing the operation of receiving a datagram from a `UdpSocket`,
+/// along with the address it came from, see `UdpSocket::recv_from` and `UdpSocket::peek_from`.
+///
+/// Cancel safe: datagrams are only received in the poll that returns them.
+pub struct RecvFromFuture<'o> {
+    io: &'o PollEvented<net::UdpSocket>,
+    buf: &'o mut [u8],
+    /// Leaves the datagram in the socket if set.
+    peek: bool,
+    waiter: Option<WaiterId>,
+}
+
+impl Future for RecvFromFuture<'_> {
+    type Output = io::Result<(usize, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let (buf, peek) = (&mut *future.buf, future.peek);
+        future
+            .io
+            .poll_io(cx, Direction::Read, &mut future.waiter, |io| match peek {
+                true => io.peek_from(buf),
+                false => io.recv_from(buf),
+            })
+    }
+}
+
+impl Drop for RecvFromFuture<'_> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.io.remove_waiter(Direction::Read, waiter);
+        }
+    }
+}
+
+/// UDP socket, registered in the reactor.
+pub struct UdpSocket {
+    io: PollEvented<net::UdpSocket>,
+}
+
+impl UdpSocket {
+    /// Binds a socket to the first address of `addr` that works,
+    /// registering it in the reactor.
+    ///
+    /// Resolving `addr` may block, like `std::net::UdpSocket::bind` does.
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<UdpSocket> {
+        let mut last_err = None;
+
+        for addr in addr.to_socket_addrs()? {
+            match net::UdpSocket::bind(addr) {
+                Ok(io) => return UdpSocket::from_mio(io),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Wraps a socket from the standard library, registering it in the reactor.
+    ///
+    /// The socket is switched to non-blocking mode.
+    pub fn from_std(socket: std::net::UdpSocket) -> io::Result<UdpSocket> {
+        socket.set_nonblocking(true)?;
+        UdpSocket::from_mio(net::UdpSocket::from_std(socket))
+    }
+
+    fn from_mio(io: net::UdpSocket) -> io::Result<UdpSocket> {
+        Ok(UdpSocket {
+            io: PollEvented::new(io)?,
+        })
+    }
+
+    /// Connects the socket to the first address of `addr` that works.
+    ///
+    /// `send` and `recv` then go to and come from that address only.
+    pub async fn connect(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let mut last_err = None;
+
+        for addr in addr.to_socket_addrs()? {
+            match self.io.get_ref().connect(addr) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Obtains the address the socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Obtains the address the socket is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+}
+
+impl UdpSocket {
+    /// Sends a datagram to `target`, returning the amount of bytes sent.
+    pub fn send_to<'a>(&'a self, buf: &'a [u8], target: SocketAddr) -> SendFuture<'a> {
+        SendFuture {
+            io: &self.io,
+            buf,
+            target: Some(target),
+            waiter: None,
+        }
+    }
+
+    /// Receives a datagram, along with the address it came from.
+    ///
+    /// Whatever does not fit in `buf` is discarded.
+    pub fn recv_from<'a>(&'a self, buf: &'a mut [u8]) -> RecvFromFuture<'a> {
+        RecvFromFuture {
+            io: &self.io,
+            buf,
+            peek: false,
+            waiter: None,
+        }
+    }
+
+    /// Same as `UdpSocket::recv_from`, without taking the datagram out of the socket.
+    pub fn peek_from<'a>(&'a self, buf: &'a mut [u8]) -> RecvFromFuture<'a> {
+        RecvFromFuture {
+            io: &self.io,
+            buf,
+            peek: true,
+            waiter: None,
+        }
+    }
+
+    /// Sends a datagram to the connected peer, see `UdpSocket::connect`.
+    pub fn send<'a>(&'a self, buf: &'a [u8]) -> SendFuture<'a> {
+        SendFuture {
+            io: &self.io,
+            buf,
+            target: None,
+            waiter: None,
+        }
+    }
+
+    /// Receives a datagram from the connected peer, see `UdpSocket::connect`.
+    pub fn recv<'a>(&'a self, buf: &'a mut [u8]) -> RecvFuture<'a> {
+        RecvFuture {
+            io: &self.io,
+            buf,
+            waiter: None,
+        }
+    }
+}
+
+// Non-blocking variants, they fail with `WouldBlock` instead of waiting.
+impl UdpSocket {
+    /// Non-blocking `UdpSocket::send_to`.
+    pub fn try_send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        self.io.get_ref().send_to(buf, target)
+    }
+
+    /// Non-blocking `UdpSocket::recv_from`.
+    pub fn try_recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.io.get_ref().recv_from(buf)
+    }
+
+    /// Non-blocking `UdpSocket::peek_from`.
+    pub fn try_peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.io.get_ref().peek_from(buf)
+    }
+
+    /// Non-blocking `UdpSocket::send`.
+    pub fn try_send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.io.get_ref().send(buf)
+    }
+
+    /// Non-blocking `UdpSocket::recv`.
+    pub fn try_recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.get_ref().recv(buf)
+    }
+}
+
+// Socket options.
+impl UdpSocket {
+    /// Allows sending to broadcast addresses.
+    pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_broadcast(on)
+    }
+
+    /// Obtains whether sending to broadcast addresses is allowed.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.io.get_ref().broadcast()
+    }
+
+    /// Sets the time to live of the datagrams sent.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_ttl(ttl)
+    }
+
+    /// Obtains the time to live of the datagrams sent.
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.io.get_ref().ttl()
+    }
+
+    /// Joins the IPv4 multicast group `multiaddr` on the interface with address `interface`,
+    /// `Ipv4Addr::UNSPECIFIED` lets the system pick the interface.
+    pub fn join_multicast_v4(&self, multiaddr: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Leaves an IPv4 multicast group, see `UdpSocket::join_multicast_v4`.
+    pub fn leave_multicast_v4(&self, multiaddr: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Joins the IPv6 multicast group `multiaddr` on the interface with index `interface`,
+    /// 0 lets the system pick the interface.
+    pub fn join_multicast_v6(&self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Leaves an IPv6 multicast group, see `UdpSocket::join_multicast_v6`.
+    pub fn leave_multicast_v6(&self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Sets whether IPv4 multicast datagrams sent also loop back to the local socket.
+    pub fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v4(on)
+    }
+
+    /// Sets the time to live of the IPv4 multicast datagrams sent.
+    pub fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_multicast_ttl_v4(ttl)
+    }
+
+    /// Sets whether IPv6 multicast datagrams sent also loop back to the local socket.
+    pub fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v6(on)
+    }
+}
+
+impl TryFrom<std::net::UdpSocket> for UdpSocket {
+    type Error = io::Error;
+
+    /// Same as `UdpSocket::from_std`.
+    fn try_from(socket: std::net::UdpSocket) -> io::Result<UdpSocket> {
+        UdpSocket::from_std(socket)
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::UdpSocket;
+    use crate::runtime::{Flavor, Runtime};
+    use std::future::Future;
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    fn run<F: Future<Output = ()> + Send + 'static>(future: F) {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    #[test]
+    fn send_to_and_recv_from() {
+        run(async {
+            let a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let b = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+            let sent = a.send_to(b"hello", b.local_addr().unwrap()).await.unwrap();
+            assert_eq!(sent, 5);
+
+            let mut buf = [0u8; 16];
+            let (read, from) = b.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"hello");
+            assert_eq!(from, a.local_addr().unwrap());
+        });
+    }
+
+    #[test]
+    fn peek_leaves_the_datagram() {
+        run(async {
+            let a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let b = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            a.send_to(b"peek", b.local_addr().unwrap()).await.unwrap();
+
+            let mut buf = [0u8; 16];
+            let (read, from) = b.peek_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"peek");
+            assert_eq!(from, a.local_addr().unwrap());
+
+            let (read, _) = b.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"peek");
+
+            // Nothing left once it was received.
+            let e = b.try_recv_from(&mut buf).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::WouldBlock);
+        });
+    }
+
+    #[test]
+    fn connected_send_and_recv() {
+        run(async {
+            let a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let b = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            a.connect(b.local_addr().unwrap()).await.unwrap();
+            b.connect(a.local_addr().unwrap()).await.unwrap();
+            assert_eq!(a.peer_addr().unwrap(), b.local_addr().unwrap());
+
+            a.send(b"one").await.unwrap();
+            let mut buf = [0u8; 16];
+            let read = b.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"one");
+        });
+    }
+}
@@ -0,0 +1,198 @@
+// crate imports
+use crate::io::{Interest, PollEvented};
+use crate::net::ucred::{self, UCred};
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use std::future::Future;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::SocketAddr;
+use std::path::Path;
+
+/// Unix datagram socket, registered in the reactor.
+///
+/// The futures are cancel safe, like the ones of `UdpSocket`.
+pub struct UnixDatagram {
+    io: PollEvented<net::UnixDatagram>,
+}
+
+impl UnixDatagram {
+    /// Binds a socket to the path `path`, registering it in the reactor.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixDatagram> {
+        UnixDatagram::from_mio(net::UnixDatagram::bind(path)?)
+    }
+
+    /// Binds a socket to `addr`, which may be in the abstract namespace on Linux,
+    /// see `std::os::linux::net::SocketAddrExt::from_abstract_name`.
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        UnixDatagram::from_mio(net::UnixDatagram::bind_addr(addr)?)
+    }
+
+    /// Creates a socket bound to no address, registering it in the reactor.
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        UnixDatagram::from_mio(net::UnixDatagram::unbound()?)
+    }
+
+    /// Creates a pair of connected sockets, both registered in the reactor.
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = net::UnixDatagram::pair()?;
+        Ok((UnixDatagram::from_mio(a)?, UnixDatagram::from_mio(b)?))
+    }
+
+    /// Wraps a socket from the standard library, registering it in the reactor.
+    ///
+    /// The socket is switched to non-blocking mode.
+    pub fn from_std(socket: std::os::unix::net::UnixDatagram) -> io::Result<UnixDatagram> {
+        socket.set_nonblocking(true)?;
+        UnixDatagram::from_mio(net::UnixDatagram::from_std(socket))
+    }
+
+    fn from_mio(io: net::UnixDatagram) -> io::Result<UnixDatagram> {
+        Ok(UnixDatagram {
+            io: PollEvented::new(io)?,
+        })
+    }
+
+    /// Connects the socket to the socket at `path`.
+    ///
+    /// `send` and `recv` then go to and come from that socket only.
+    pub fn connect(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.io.get_ref().connect(path)
+    }
+
+    /// Obtains the address the socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Obtains the address the socket is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    /// Obtains the credentials of the process on the other end of a connected socket.
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        ucred::peer_cred(self.as_raw_fd())
+    }
+
+    /// Shuts down the reading, writing, or both halves of the socket.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().shutdown(how)
+    }
+}
+
+impl UnixDatagram {
+    /// Sends a datagram to the socket at `path`, returning the amount of bytes sent.
+    pub fn send_to<'a>(
+        &'a self,
+        buf: &'a [u8],
+        path: &'a Path,
+    ) -> impl Future<Output = io::Result<usize>> + 'a {
+        self.io
+            .async_io(Interest::WRITABLE, move |io| io.send_to(buf, path))
+    }
+
+    /// Receives a datagram, along with the address it came from.
+    ///
+    /// Whatever does not fit in `buf` is discarded.
+    pub fn recv_from<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + 'a {
+        self.io
+            .async_io(Interest::READABLE, move |io| io.recv_from(buf))
+    }
+
+    /// Sends a datagram to the connected peer.
+    pub fn send<'a>(&'a self, buf: &'a [u8]) -> impl Future<Output = io::Result<usize>> + 'a {
+        self.io.async_io(Interest::WRITABLE, move |io| io.send(buf))
+    }
+
+    /// Receives a datagram from the connected peer.
+    pub fn recv<'a>(&'a self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a {
+        self.io.async_io(Interest::READABLE, move |io| io.recv(buf))
+    }
+
+    /// Non-blocking `UnixDatagram::send_to`.
+    pub fn try_send_to(&self, buf: &[u8], path: impl AsRef<Path>) -> io::Result<usize> {
+        self.io.get_ref().send_to(buf, path)
+    }
+
+    /// Non-blocking `UnixDatagram::recv_from`.
+    pub fn try_recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.io.get_ref().recv_from(buf)
+    }
+
+    /// Non-blocking `UnixDatagram::send`.
+    pub fn try_send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.io.get_ref().send(buf)
+    }
+
+    /// Non-blocking `UnixDatagram::recv`.
+    pub fn try_recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.get_ref().recv(buf)
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl TryFrom<std::os::unix::net::UnixDatagram> for UnixDatagram {
+    type Error = io::Error;
+
+    /// Same as `UnixDatagram::from_std`.
+    fn try_from(socket: std::os::unix::net::UnixDatagram) -> io::Result<UnixDatagram> {
+        UnixDatagram::from_std(socket)
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::UnixDatagram;
+    use crate::runtime::{Flavor, Runtime};
+    use std::future::Future;
+    use std::time::Duration;
+
+    fn run<F: Future<Output = ()> + Send + 'static>(future: F) {
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+
+    #[test]
+    fn pair_round_trip() {
+        run(async {
+            let (a, b) = UnixDatagram::pair().unwrap();
+            a.send(b"ping").await.unwrap();
+
+            let mut buf = [0u8; 16];
+            let read = b.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"ping");
+        });
+    }
+
+    #[test]
+    fn send_to_a_bound_socket() {
+        let path = std::env::temp_dir().join(format!("apple-dgram-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        run(async move {
+            let server = UnixDatagram::bind(&path).unwrap();
+            let client = UnixDatagram::unbound().unwrap();
+            client.send_to(b"hello", &path).await.unwrap();
+
+            let mut buf = [0u8; 16];
+            let (read, _) = server.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..read], b"hello");
+            std::fs::remove_file(&path).unwrap();
+        });
+    }
+}
@@ -0,0 +1,113 @@
+// crate imports
+use crate::io::{Interest, PollEvented};
+use crate::net::UnixStream;
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::SocketAddr;
+use std::path::Path;
+
+/// Unix socket listening for connections.
+pub struct UnixListener {
+    io: PollEvented<net::UnixListener>,
+}
+
+impl UnixListener {
+    /// Binds a listener to the socket at `path`, registering it in the reactor.
+    ///
+    /// Fails if `path` already exists.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixListener> {
+        UnixListener::from_mio(net::UnixListener::bind(path)?)
+    }
+
+    /// Binds a listener to `addr`, which may be in the abstract namespace on Linux,
+    /// see `std::os::linux::net::SocketAddrExt::from_abstract_name`.
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixListener> {
+        UnixListener::from_mio(net::UnixListener::bind_addr(addr)?)
+    }
+
+    /// Wraps a listener from the standard library, registering it in the reactor.
+    ///
+    /// The listener is switched to non-blocking mode.
+    pub fn from_std(listener: std::os::unix::net::UnixListener) -> io::Result<UnixListener> {
+        listener.set_nonblocking(true)?;
+        UnixListener::from_mio(net::UnixListener::from_std(listener))
+    }
+
+    fn from_mio(io: net::UnixListener) -> io::Result<UnixListener> {
+        Ok(UnixListener {
+            io: PollEvented::with_interest(io, Interest::READABLE)?,
+        })
+    }
+
+    /// Accepts a new connection, along with the address of the peer.
+    ///
+    /// The accepted `UnixStream` is registered in the reactor already.
+    /// Cancel safe, like `TcpListener::accept`, and a connection wakes every task accepting too.
+    pub async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        let (io, addr) = self
+            .io
+            .async_io(Interest::READABLE, |io| io.accept())
+            .await?;
+        Ok((UnixStream::from_mio(io)?, addr))
+    }
+
+    /// Obtains the address the listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+}
+
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl TryFrom<std::os::unix::net::UnixListener> for UnixListener {
+    type Error = io::Error;
+
+    /// Same as `UnixListener::from_std`.
+    fn try_from(listener: std::os::unix::net::UnixListener) -> io::Result<UnixListener> {
+        UnixListener::from_std(listener)
+    }
+}
+
+#[cfg(all(test, feature = "rt"))]
+mod tests {
+    use super::UnixListener;
+    use crate::io::{AsyncReadExt, AsyncWriteExt};
+    use crate::net::UnixStream;
+    use crate::runtime::{Flavor, Runtime};
+    use std::time::Duration;
+
+    #[test]
+    fn accept_and_round_trip() {
+        let path = std::env::temp_dir().join(format!("apple-listener-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let runtime = Runtime::new(Flavor::CurrentThread, 1);
+        let future = async move {
+            let listener = UnixListener::bind(&path).unwrap();
+            let mut client = UnixStream::connect(&path).await.unwrap();
+            let (mut server, _) = listener.accept().await.unwrap();
+
+            client.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+
+            server.write_all(b"pong").await.unwrap();
+            client.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"pong");
+            std::fs::remove_file(&path).unwrap();
+        };
+        runtime
+            .block_on_timeout(future, Duration::from_secs(5))
+            .expect("test timed out");
+    }
+}
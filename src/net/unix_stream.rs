@@ -0,0 +1,194 @@
+// crate imports
+use crate::io::reactor::Direction;
+use crate::io::{Interest, PollEvented};
+use crate::net::scm;
+use crate::net::ucred::{self, UCred};
+
+// Mio imports
+use mio::net;
+
+/// std imports
+use std::future::{poll_fn, Future};
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, OwnedFd, RawFd};
+use std::os::unix::net::SocketAddr;
+use std::path::Path;
+
+/// Unix socket connected to a listener, or to the other end of a pair.
+pub struct UnixStream {
+    io: PollEvented<net::UnixStream>,
+}
+
+impl UnixStream {
+    /// Opens a connection to the socket at `path`, registering the stream in the reactor.
+    pub async fn connect(path: impl AsRef<Path>) -> io::Result<UnixStream> {
+        UnixStream::connected(net::UnixStream::connect(path)?).await
+    }
+
+    /// Opens a connection to `addr`, which may be in the abstract namespace on Linux,
+    /// see `std::os::linux::net::SocketAddrExt::from_abstract_name`.
+    pub async fn connect_addr(addr: &SocketAddr) -> io::Result<UnixStream> {
+        UnixStream::connected(net::UnixStream::connect_addr(addr)?).await
+    }
+
+    /// Waits for a connect that was started to finish.
+    async fn connected(io: net::UnixStream) -> io::Result<UnixStream> {
+        let stream = UnixStream::from_mio(io)?;
+
+        // Dropping the stream deregisters it, which takes the waiter along.
+        let mut waiter = None;
+        poll_fn(|cx| stream.io.poll_ready(cx, Direction::Write, &mut waiter)).await?;
+
+        match stream.io.get_ref().take_error()? {
+            Some(e) => Err(e),
+            None => Ok(stream),
+        }
+    }
+
+    /// Creates a pair of connected streams, both registered in the reactor.
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = net::UnixStream::pair()?;
+        Ok((UnixStream::from_mio(a)?, UnixStream::from_mio(b)?))
+    }
+
+    /// Wraps a stream from the standard library, registering it in the reactor.
+    ///
+    /// The stream is switched to non-blocking mode.
+    pub fn from_std(stream: std::os::unix::net::UnixStream) -> io::Result<UnixStream> {
+        stream.set_nonblocking(true)?;
+        UnixStream::from_mio(net::UnixStream::from_std(stream))
+    }
+
+    pub(crate) fn from_mio(io: net::UnixStream) -> io::Result<UnixStream> {
+        Ok(UnixStream {
+            io: PollEvented::new(io)?,
+        })
+    }
+
+    /// Obtains the address of the peer of the stream.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    /// Obtains the local address of the stream.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Obtains the credentials of the process on the other end of the stream.
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        ucred::peer_cred(self.as_raw_fd())
+    }
+
+    /// Shuts down the reading, writing, or both halves of the stream.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().shutdown(how)
+    }
+
+    /// Sends `buf` along with the file descriptors `fds`, returning the amount of bytes sent.
+    ///
+    /// The descriptors arrive with the first byte, `buf` can't be empty.
+    /// At most 253 of them fit in one call.
+    pub fn send_with_fds<'a>(
+        &'a self,
+        buf: &'a [u8],
+        fds: &'a [RawFd],
+    ) -> impl Future<Output = io::Result<usize>> + 'a {
+        self.io.async_io(Interest::WRITABLE, move |io| {
+            scm::send_with_fds(io.as_raw_fd(), buf, fds)
+        })
+    }
+
+    /// Receives into `buf`, along with up to `max_fds` file descriptors sent by `send_with_fds`.
+    ///
+    /// Fails if more descriptors came, closing them.
+    /// Cancel safe: nothing is received unless the future completes.
+    pub fn recv_with_fds<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+        max_fds: usize,
+    ) -> impl Future<Output = io::Result<(usize, Vec<OwnedFd>)>> + 'a {
+        self.io.async_io(Interest::READABLE, move |io| {
+            scm::recv_with_fds(io.as_raw_fd(), buf, max_fds)
+        })
+    }
+}
+
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl_async_stream!(UnixStream);
+
+impl io::Read for &UnixStream {
+    /// Works by calling the underlying `io`'s `read` function.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut io = self.io.get_ref();
+        io.read(buf)
+    }
+}
+
+impl io::Write for &UnixStream {
+    /// Works by calling the underlying `io`'s `write` function.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut io = self.io.get_ref();
+        io.write(buf)
+    }
+
+    /// Works by calling the underlying `io`'s `flush` function.
+    fn flush(&mut self) -> io::Result<()> {
+        let mut io = self.io.get_ref();
+        io.flush()
+    }
+}
+
+impl TryFrom<std::os::unix::net::UnixStream> for UnixStream {
+    type Error = io::Error;
+
+    /// Same as `UnixStream::from_std`.
+    fn try_from(stream: std::os::unix::net::UnixStream) -> io::Result<UnixStream> {
+        UnixStream::from_std(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnixStream;
+    use crate::io::{AsyncReadExt, Reactor};
+    use futures::task::noop_waker;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn cancelled_read_removes_its_waiter() {
+        let (mut a, _b) = UnixStream::pair().unwrap();
+        let token = a.io.registration().token();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut buf = [0u8; 4];
+        {
+            let mut read = pin!(a.read_exact(&mut buf));
+            assert!(matches!(read.as_mut().poll(&mut cx), Poll::Pending));
+            assert!(Reactor::has_waiters(token));
+        }
+        assert!(!Reactor::has_waiters(token));
+    }
+
+    #[test]
+    fn peer_cred_of_a_pair() {
+        let (a, b) = UnixStream::pair().unwrap();
+        let cred = a.peer_cred().unwrap();
+
+        // Safety: both calls only return ids of the process.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        assert_eq!((cred.uid, cred.gid), (uid, gid));
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(cred.pid, Some(std::process::id() as i32));
+        assert_eq!(b.peer_cred().unwrap(), cred);
+    }
+}
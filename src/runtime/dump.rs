@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// Snapshot of a single task, as seen by `Runtime::dump`.
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    /// Identifier of the task within its runtime.
+    pub id: usize,
+
+    /// Type name of the future the task is running.
+    pub name: &'static str,
+
+    /// Scheduling state of the task.
+    pub state: &'static str,
+
+    /// Amount of times the task was polled.
+    pub polls: usize,
+}
+
+/// Snapshot of every live task of a runtime.
+///
+/// Mostly useful to find out what a hung program is waiting on.
+#[derive(Debug, Clone, Default)]
+pub struct TaskDump {
+    pub tasks: Vec<TaskInfo>,
+}
+
+impl fmt::Display for TaskDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Task dump ({} live tasks):", self.tasks.len())?;
+        for task in &self.tasks {
+            writeln!(
+                f,
+                "  task {} [{}] state: {}, polls: {}",
+                task.id, task.name, task.state, task.polls
+            )?;
+        }
+        Ok(())
+    }
+}
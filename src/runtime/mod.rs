@@ -1,14 +1,24 @@
-pub mod worker_thread;
+#[cfg(feature = "rt-multi-thread")]
+mod worker_thread;
+#[cfg(feature = "rt-multi-thread")]
 use worker_thread::WorkerThread;
 
-pub mod runtime;
-pub use runtime::Runtime;
+#[allow(clippy::module_inception)]
+mod runtime;
+pub use runtime::{Elapsed, Flavor, Runtime};
 
-pub mod mut_cell;
-pub use mut_cell::MutCell;
+mod mut_cell;
+use mut_cell::MutCell;
 
-pub mod task_handle;
+mod task_handle;
 pub use task_handle::TaskHandle;
 
-pub mod thread_pool;
-pub use thread_pool::ThreadPool;
+#[cfg(feature = "rt-multi-thread")]
+mod thread_pool;
+#[cfg(feature = "rt-multi-thread")]
+use thread_pool::ThreadPool;
+
+mod dump;
+pub use dump::{TaskDump, TaskInfo};
+
+mod queue;
@@ -26,11 +26,6 @@ impl<T> MutCell<T> {
     pub unsafe fn get_mut(&self) -> &mut T {
         unsafe { &mut *self.data.get() }
     }
-
-    /// Obtains an immutable reference to the type within.
-    pub fn get(&self) -> &T {
-        unsafe { &*self.data.get() }
-    }
 }
 
 impl<T> Deref for MutCell<T> {
@@ -0,0 +1,381 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{self, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+/// Capacity of the lock-free part of the queue, must be a power of two.
+const CAPACITY: usize = 1024;
+
+/// Slot of the ring buffer.
+///
+/// `seq` tells which lap of the ring the slot is in,
+/// and whether it is waiting for a push or a pop.
+struct Slot<T> {
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Multi-producer multi-consumer queue used as the run queue of the Runtime.
+///
+/// Pushing and popping go through a lock-free bounded ring buffer,
+/// and only fall back to a locked overflow list once the ring is full.
+/// Consumers can block on the queue until something is pushed.
+pub(crate) struct Queue<T> {
+    buffer: Box<[Slot<T>]>,
+
+    /// Position of the next pop.
+    head: AtomicUsize,
+
+    /// Position of the next push.
+    tail: AtomicUsize,
+
+    /// Items pushed while the ring buffer was full.
+    overflow: Mutex<VecDeque<T>>,
+    overflow_len: AtomicUsize,
+
+    /// Amount of consumers blocked in `pop_blocking`.
+    sleepers: AtomicUsize,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+// Safety: values are only ever accessed by the thread that claimed their slot.
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    /// Creates a new, empty Queue.
+    pub(crate) fn new() -> Queue<T> {
+        let buffer = (0..CAPACITY)
+            .map(|i| Slot {
+                seq: AtomicUsize::new(i),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+
+        Queue {
+            buffer,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflow: Mutex::new(VecDeque::new()),
+            overflow_len: AtomicUsize::new(0),
+            sleepers: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Pushes a value into the queue, waking up a blocked consumer if there is one.
+    pub(crate) fn push(&self, value: T) {
+        if let Err(value) = self.push_ring(value) {
+            let mut overflow = self.overflow.lock().expect("failed overflow lock");
+            overflow.push_back(value);
+            self.overflow_len.fetch_add(1, Ordering::Release);
+        }
+
+        // Pairs with the fence in `pop_blocking`,
+        // either we see the sleeper or it sees our value.
+        atomic::fence(Ordering::SeqCst);
+        if self.sleepers.load(Ordering::Relaxed) > 0 {
+            let _lock = self.lock.lock().expect("failed queue lock");
+            self.condvar.notify_one();
+        }
+    }
+
+    /// Pops a value from the queue, without blocking.
+    pub(crate) fn pop(&self) -> Option<T> {
+        if let Some(value) = self.pop_ring() {
+            return Some(value);
+        }
+
+        if self.overflow_len.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+
+        let mut overflow = self.overflow.lock().expect("failed overflow lock");
+        let value = overflow.pop_front();
+        if value.is_some() {
+            self.overflow_len.fetch_sub(1, Ordering::Release);
+        }
+        value
+    }
+
+    /// Checks if the queue is empty, which may be stale by the time it returns.
+    #[cfg(feature = "rt-drive-io")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+            && self.overflow_len.load(Ordering::Acquire) == 0
+    }
+
+    /// Pops a value from the queue, blocking until one is available.
+    ///
+    /// Gives up and returns `None` once `deadline` has passed.
+    pub(crate) fn pop_blocking(&self, deadline: Option<Instant>) -> Option<T> {
+        loop {
+            if let Some(value) = self.pop() {
+                return Some(value);
+            }
+
+            let lock = self.lock.lock().expect("failed queue lock");
+            self.sleepers.fetch_add(1, Ordering::Relaxed);
+            atomic::fence(Ordering::SeqCst);
+
+            // Something may have been pushed before we were counted as a sleeper.
+            if let Some(value) = self.pop() {
+                self.sleepers.fetch_sub(1, Ordering::Relaxed);
+                return Some(value);
+            }
+
+            let timed_out = match deadline {
+                None => {
+                    let _lock = self.condvar.wait(lock).expect("failed queue wait");
+                    false
+                }
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    let (_lock, result) = self
+                        .condvar
+                        .wait_timeout(lock, timeout)
+                        .expect("failed queue wait");
+                    result.timed_out()
+                }
+            };
+
+            self.sleepers.fetch_sub(1, Ordering::Relaxed);
+            if timed_out {
+                return self.pop();
+            }
+        }
+    }
+
+    /// Pushes into the ring buffer, returns the value back if it is full.
+    fn push_ring(&self, value: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & (CAPACITY - 1)];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = (seq as isize).wrapping_sub(pos as isize);
+
+            if diff == 0 {
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Safety: the slot was claimed by the compare exchange above.
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(actual) => pos = actual,
+                }
+            } else if diff < 0 {
+                // The slot still holds a value from the previous lap.
+                return Err(value);
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pops from the ring buffer, returns `None` if it is empty.
+    fn pop_ring(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & (CAPACITY - 1)];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = (seq as isize).wrapping_sub(pos.wrapping_add(1) as isize);
+
+            if diff == 0 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Safety: the slot was claimed by the compare exchange above,
+                        // and its value was written before `seq` was published.
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.seq
+                            .store(pos.wrapping_add(CAPACITY), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(actual) => pos = actual,
+                }
+            } else if diff < 0 {
+                // Nothing was pushed into this slot yet.
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        while self.pop_ring().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Queue, CAPACITY};
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fifo_on_one_thread() {
+        let queue = Queue::new();
+        for i in 0..100 {
+            queue.push(i);
+        }
+
+        for i in 0..100 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn ring_wraps_around() {
+        let queue = Queue::new();
+        for lap in 0..5 {
+            for i in 0..CAPACITY {
+                queue.push(lap * CAPACITY + i);
+            }
+            for i in 0..CAPACITY {
+                assert_eq!(queue.pop(), Some(lap * CAPACITY + i));
+            }
+        }
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn overflow_once_the_ring_is_full() {
+        let queue = Queue::new();
+        for i in 0..CAPACITY + 10 {
+            queue.push(i);
+        }
+        assert_eq!(queue.overflow.lock().unwrap().len(), 10);
+
+        // The ring is drained first, then the overflow in the order it was pushed.
+        for i in 0..CAPACITY + 10 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.overflow_len.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn overflow_is_not_lost_when_the_ring_frees_up() {
+        let queue = Queue::new();
+        for i in 0..CAPACITY + 3 {
+            queue.push(i);
+        }
+
+        // Room in the ring again, new values go there while the overflow still holds some.
+        assert_eq!(queue.pop(), Some(0));
+        queue.push(CAPACITY + 3);
+
+        let mut popped: Vec<usize> = std::iter::from_fn(|| queue.pop()).collect();
+        popped.sort_unstable();
+        assert_eq!(popped, (1..CAPACITY + 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn many_producers_and_consumers() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 20_000;
+
+        let queue = Arc::new(Queue::new());
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        queue.push(p * PER_PRODUCER + i);
+                    }
+                })
+            })
+            .collect();
+
+        let popped = Arc::new(AtomicUsize::new(0));
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                let popped = Arc::clone(&popped);
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while popped.load(Ordering::Relaxed) < PRODUCERS * PER_PRODUCER {
+                        let deadline = Instant::now() + Duration::from_millis(10);
+                        if let Some(value) = queue.pop_blocking(Some(deadline)) {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            seen.push(value);
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut all = HashSet::new();
+        for consumer in consumers {
+            for value in consumer.join().unwrap() {
+                assert!(all.insert(value), "{value} popped twice");
+            }
+        }
+
+        assert_eq!(all.len(), PRODUCERS * PER_PRODUCER);
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn pop_blocking_wakes_up_on_push() {
+        let queue = Arc::new(Queue::new());
+        let consumer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || queue.pop_blocking(None))
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        queue.push(7);
+        assert_eq!(consumer.join().unwrap(), Some(7));
+    }
+
+    #[test]
+    fn pop_blocking_gives_up_at_the_deadline() {
+        let queue = Queue::<u32>::new();
+        let start = Instant::now();
+        assert_eq!(
+            queue.pop_blocking(Some(start + Duration::from_millis(50))),
+            None
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn drop_releases_the_values_left() {
+        let value = Arc::new(());
+        let queue = Queue::new();
+        for _ in 0..CAPACITY + 5 {
+            queue.push(Arc::clone(&value));
+        }
+
+        drop(queue);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
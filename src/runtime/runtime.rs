@@ -1,21 +1,33 @@
-use crate::io::Handle;
 use crate::io::Reactor;
+use crate::runtime::queue::Queue;
+use crate::runtime::task_handle::JoinState;
 use crate::runtime::MutCell;
 use crate::runtime::TaskHandle;
+#[cfg(feature = "rt-multi-thread")]
 use crate::runtime::ThreadPool;
+#[cfg(all(feature = "rt-multi-thread", feature = "rt-drive-io"))]
+use crate::runtime::WorkerThread;
+use crate::runtime::{TaskDump, TaskInfo};
 
 use async_lock::OnceCell;
 use futures::task::{self, ArcWake};
 
 use mio::event::Source;
-use mio::{Interest, Registry};
+use mio::{Interest, Registry, Token};
 
+use slab::Slab;
+
+use std::cell::RefCell;
+use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 
-use std::sync::mpmc::{self, Receiver, Sender};
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "rt-drive-io")]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
 use std::io::Result as IoResult;
 
@@ -24,229 +36,646 @@ type RuntimeFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 
 /// Trait for `FutureTask` so we can use different result values.
 trait FutureTaskTrait {
-    fn poll(&mut self, cx: &mut Context<'_>);
-    fn current_poll(&self) -> Poll<()>;
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()>;
 }
 
 /// Underlying task for the `Task` struct.
 struct FutureTask<T> {
-    poll: Poll<()>,
-    ft: RuntimeFuture<T>,
+    ft: Option<RuntimeFuture<T>>,
+    join: Arc<Mutex<JoinState<T>>>,
 }
 
 impl<T> FutureTask<T> {
     /// Creates a new `FutureTask` with a type of `RuntimeFuture`
-    /// `RuntimeFuture` is a type alias referring to `Pin<Box<dyn Future<Output = ()> + Send`
-    fn new(f: RuntimeFuture<T>) -> FutureTask<T> {
-        FutureTask {
-            poll: Poll::Pending,
-            ft: f,
-        }
+    /// `RuntimeFuture` is a type alias referring to `Pin<Box<dyn Future<Output = T> + Send`
+    fn new(f: RuntimeFuture<T>, join: Arc<Mutex<JoinState<T>>>) -> FutureTask<T> {
+        FutureTask { ft: Some(f), join }
     }
 }
 
 impl<T> FutureTaskTrait for FutureTask<T> {
     /// Polls the `Future` inside the `FutureTask`
-    /// Only polls it if the poll returned earlier was `Poll::Pending`
-    fn poll(&mut self, cx: &mut Context<'_>) {
-        if self.poll.is_pending() {
-            let poll = self.ft.as_mut().poll(cx);
-            self.poll = match poll.is_ready() {
-                true => Poll::Ready(()),
-                false => Poll::Pending,
+    /// Once it completes, the output is handed over to the `TaskHandle`
+    /// and the `Future` is dropped.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let ft = match self.ft.as_mut() {
+            Some(ft) => ft,
+            None => return Poll::Ready(()),
+        };
+
+        match ft.as_mut().poll(cx) {
+            Poll::Ready(output) => {
+                self.ft = None;
+                self.join
+                    .lock()
+                    .expect("failed join state lock")
+                    .complete(output);
+                Poll::Ready(())
             }
+            Poll::Pending => Poll::Pending,
         }
     }
+}
 
-    fn current_poll(&self) -> Poll<()> {
-        self.poll
+impl<T> Drop for FutureTask<T> {
+    fn drop(&mut self) {
+        if self.ft.is_some() {
+            if let Ok(mut join) = self.join.lock() {
+                join.cancel()
+            }
+        }
     }
 }
 
+// States of a `Task`.
+/// Waiting for a wakeup.
+const IDLE: usize = 0;
+/// Sitting in a queue, waiting to be polled.
+const SCHEDULED: usize = 1;
+/// Being polled by a thread.
+const RUNNING: usize = 2;
+/// Woken up while being polled, will be rescheduled afterwards.
+const NOTIFIED: usize = 3;
+/// Finished, will never be polled again.
+const COMPLETE: usize = 4;
+
 /// Task struct used by the Runtime
 pub struct Task {
     taskft: MutCell<Box<dyn FutureTaskTrait + 'static>>,
-    exec: Sender<Arc<Task>>,
-    waker: MutCell<Waker>,
+    shared: Weak<Shared>,
+    state: AtomicUsize,
+
+    /// Set for the main task of `Runtime::block_on`, which is only polled by its thread.
+    #[cfg_attr(
+        not(all(feature = "rt-multi-thread", feature = "rt-drive-io")),
+        allow(dead_code)
+    )]
+    pinned: bool,
+
+    /// Used for task dumps.
+    id: usize,
+    name: &'static str,
+    polls: AtomicUsize,
 }
 
 impl std::ops::Drop for Task {
     fn drop(&mut self) {
-        println!("Dropped a task!");
-        self.waker.get().wake_by_ref();
+        if let Some(shared) = self.shared.upgrade() {
+            shared
+                .tasks
+                .lock()
+                .expect("failed task registry lock")
+                .try_remove(self.id);
+        }
     }
 }
 
 impl Task {
-    /// Creates a new `Task` from a `Sender<TaskRelated>` and a `FutureTask`
-    fn new(tsft: Box<dyn FutureTaskTrait + 'static>, exec: Sender<Arc<Task>>) -> Task {
-        let taskft = unsafe { MutCell::new(tsft) };
-        Task {
-            taskft,
-            exec,
-            waker: unsafe { MutCell::new(Waker::noop().clone()) },
-        }
-    }
-
-    /// Convenience function to create a `Arc<Task>` from a type implementing `Future<Output = ()> + Send + 'static`
-    fn arc_new<F, T: 'static>(future: F, sender: Sender<Arc<Task>>) -> Arc<Task>
+    /// Convenience function to create a `Arc<Task>` from a type implementing `Future<Output = T> + Send + 'static`
+    /// The task is registered in the task registry of the runtime.
+    fn arc_new<F, T: 'static>(
+        future: F,
+        join: Arc<Mutex<JoinState<T>>>,
+        shared: &Arc<Shared>,
+        pinned: bool,
+    ) -> Arc<Task>
     where
         F: Future<Output = T> + Send + 'static,
     {
-        let task = Task::new(Box::new(FutureTask::new(Box::pin(future))), sender.clone());
-
-        Arc::new(task)
+        let mut tasks = shared.tasks.lock().expect("failed task registry lock");
+        let entry = tasks.vacant_entry();
+
+        let tsft: Box<dyn FutureTaskTrait> = Box::new(FutureTask::new(Box::pin(future), join));
+        let task = Arc::new(Task {
+            taskft: unsafe { MutCell::new(tsft) },
+            shared: Arc::downgrade(shared),
+            state: AtomicUsize::new(SCHEDULED),
+            pinned,
+            id: entry.key(),
+            name: std::any::type_name::<F>(),
+            polls: AtomicUsize::new(0),
+        });
+
+        entry.insert(Arc::downgrade(&task));
+        task
     }
 
     /// Sends the `Task` to the Runtime
-    fn send(self: &Arc<Self>) -> Result<(), mpmc::SendError<Arc<Task>>> {
-        self.exec.send(self.clone())
-    }
+    ///
+    /// Does nothing if the runtime is already gone.
+    ///
+    /// With `rt-drive-io`, a task woken on a worker thread goes to the local queue of that worker.
+    fn send(self: &Arc<Self>) {
+        if let Some(shared) = self.shared.upgrade() {
+            #[cfg(all(feature = "rt-multi-thread", feature = "rt-drive-io"))]
+            if !self.pinned && WorkerThread::push_local(self) {
+                return;
+            }
 
-    pub(crate) fn change_waker(self: &Arc<Self>, waker: &Waker) {
-        if !self.waker.will_wake(waker) {
-            // Safety: Only one thread will access this.
-            unsafe { *self.waker.get_mut() = waker.clone() }
+            shared.push(self.clone())
         }
     }
 
     /// Polls the internal `FutureTask`
+    ///
+    /// Only polls if the task is scheduled, so a task is never polled by two threads at once.
     pub(crate) fn poll(self: Arc<Self>) {
-        if self.taskft.current_poll().is_pending() {
-            let waker = task::waker(self.clone());
-            let mut cx = Context::from_waker(&waker);
+        if self
+            .state
+            .compare_exchange(SCHEDULED, RUNNING, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+
+        // The runtime is gone, nothing would ever poll the task again.
+        let shared = match self.shared.upgrade() {
+            Some(shared) => shared,
+            None => {
+                self.state.store(COMPLETE, Ordering::Release);
+                return;
+            }
+        };
+        let _enter = Shared::enter(shared);
+
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        let waker = task::waker(self.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Safety:
+        // The state above lets only 1 thread access this mutably.
+        let poll = unsafe { self.taskft.get_mut().poll(&mut cx) };
+        if poll.is_ready() {
+            self.state.store(COMPLETE, Ordering::Release);
+            return;
+        }
 
-            // Safety:
-            // This will let only 1 thread access this mutably.
-            unsafe { self.taskft.get_mut().poll(&mut cx) };
+        // Woken up while we were polling, so it goes back to the queue.
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            self.state.store(SCHEDULED, Ordering::Release);
+            self.send();
         }
     }
 
-    /// Checks if the Task is Pending or Ready
-    pub(crate) fn ready(self: &Arc<Task>) -> bool {
-        !self.taskft.current_poll().is_pending()
+    /// Snapshot of the task for a `TaskDump`.
+    fn info(&self) -> TaskInfo {
+        let state = match self.state.load(Ordering::Acquire) {
+            IDLE => "idle",
+            SCHEDULED => "scheduled",
+            RUNNING => "running",
+            NOTIFIED => "notified",
+            _ => "complete",
+        };
+
+        TaskInfo {
+            id: self.id,
+            name: self.name,
+            state,
+            polls: self.polls.load(Ordering::Relaxed),
+        }
     }
 }
 
 impl ArcWake for Task {
     fn wake_by_ref(arc_self: &Arc<Self>) {
-        match arc_self.send() {
-            Ok(_) => {}
-            Err(e) => panic!("{}", e),
+        let mut state = arc_self.state.load(Ordering::Acquire);
+        loop {
+            let next = match state {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return,
+            };
+
+            match arc_self.state.compare_exchange_weak(
+                state,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
+        }
+
+        // A running task gets rescheduled by the thread polling it.
+        if state == IDLE {
+            arc_self.send();
         }
     }
 }
 
-/// Async runtime.
-pub struct Runtime {
-    /// Channels to send and receive tasks
-    receiver: Receiver<Arc<Task>>,
-    sender: Sender<Arc<Task>>,
+/// Scheduler flavor of a `Runtime`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Every task is polled on the thread calling `Runtime::block_on`.
+    CurrentThread,
+
+    /// Spawned tasks are distributed across a pool of worker threads.
+    #[cfg(feature = "rt-multi-thread")]
+    MultiThread,
+}
+
+/// Error returned by `Runtime::block_on_timeout` when the deadline passed.
+#[derive(Debug, Clone)]
+pub struct Elapsed {
+    dump: TaskDump,
+}
+
+impl Elapsed {
+    /// Task dump of the runtime, taken when the deadline passed.
+    pub fn dump(&self) -> &TaskDump {
+        &self.dump
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// State shared between a `Runtime` and its tasks.
+pub(crate) struct Shared {
+    /// Queue of tasks to be polled by `Runtime::block_on`.
+    queue: Queue<Arc<Task>>,
+
+    /// Thread pool, not present for `Flavor::CurrentThread`.
+    #[cfg(feature = "rt-multi-thread")]
+    pool: Option<Mutex<ThreadPool>>,
+
+    /// Every live task, for task dumps.
+    tasks: Mutex<Slab<Weak<Task>>>,
+
+    /// Set while `Runtime::block_on` waits for the queue in the reactor's poll.
+    #[cfg(feature = "rt-drive-io")]
+    parked: AtomicBool,
+}
+
+thread_local! {
+    // Runtime of the task currently being polled, or of the `block_on` call.
+    static CURRENT: RefCell<Option<Arc<Shared>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous runtime context when dropped.
+struct EnterGuard {
+    previous: Option<Arc<Shared>>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+impl Shared {
+    /// Makes `shared` the current runtime of this thread until the guard is dropped.
+    fn enter(shared: Arc<Shared>) -> EnterGuard {
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(shared));
+        EnterGuard { previous }
+    }
+
+    /// Obtains the current runtime, falling back to the global one.
+    fn current() -> Arc<Shared> {
+        match CURRENT.with(|current| current.borrow().clone()) {
+            Some(shared) => shared,
+            None => Arc::clone(&Runtime::get().shared),
+        }
+    }
+
+    /// Spawns a task onto this runtime.
+    fn spawn<F, T: Send + 'static>(self: &Arc<Self>, future: F) -> TaskHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let join = JoinState::arc_new();
+        let task = Task::arc_new(future, Arc::clone(&join), self, false);
+
+        match self.send_task(task) {
+            Ok(()) => {}
+            Err(e) => panic!("{e}"),
+        };
+
+        TaskHandle::new(join)
+    }
+
+    /// Send a task to the thread pool.
+    /// Without a thread pool, the task is polled by `Runtime::block_on`.
+    fn send_task(&self, task: Arc<Task>) -> IoResult<()> {
+        #[cfg(feature = "rt-multi-thread")]
+        if let Some(ref pool) = self.pool {
+            return pool
+                .lock()
+                .expect("Failed lock on mutex containing the thread pool")
+                .distribute_task(task);
+        }
+
+        self.push(task);
+        Ok(())
+    }
+
+    /// Pushes a task into the queue of `Runtime::block_on`.
+    fn push(&self, task: Arc<Task>) {
+        self.queue.push(task);
+
+        #[cfg(feature = "rt-drive-io")]
+        Reactor::unpark(&self.parked);
+    }
+
+    /// Pops a task woken by the reactor events that already came in, without blocking.
+    ///
+    /// Does nothing while a polling thread holds the reactor,
+    /// it wakes the tasks as the events come in then.
+    fn pop_woken(&self) -> Option<Arc<Task>> {
+        Reactor::turn(Some(Duration::ZERO)).ok()?;
+        self.queue.pop()
+    }
+
+    /// Pops a task from the queue of `Runtime::block_on`, blocking until one is available.
+    ///
+    /// Gives up and returns `None` once `deadline` has passed.
+    #[cfg(not(feature = "rt-drive-io"))]
+    fn pop_blocking(&self, deadline: Option<Instant>) -> Option<Arc<Task>> {
+        self.queue.pop_blocking(deadline)
+    }
 
-    /// I/O Driver and handle.
-    driver: Reactor,
-    handle: Arc<Handle>,
+    /// Same as above, but polls the reactor while waiting, unless another thread is.
+    #[cfg(feature = "rt-drive-io")]
+    fn pop_blocking(&self, deadline: Option<Instant>) -> Option<Arc<Task>> {
+        loop {
+            if let Some(task) = self.queue.pop() {
+                return Some(task);
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) if !timeout.is_zero() => Some(timeout),
+                    _ => return None,
+                },
+                None => None,
+            };
+
+            if !Reactor::drive(timeout, &self.parked, || self.queue.is_empty()) {
+                return self.queue.pop_blocking(deadline);
+            }
+        }
+    }
+}
+
+/// Task standing in for the future of `Runtime::block_on_local`, it never completes.
+struct BlockOn;
+
+impl Future for BlockOn {
+    type Output = ();
 
-    /// Thread pool.
-    pool: Mutex<ThreadPool>,
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Pending
+    }
+}
+
+/// Async runtime.
+pub struct Runtime {
+    shared: Arc<Shared>,
 }
 
 // Global variable to hold the Runtime.
 static RUNTIME: OnceCell<Runtime> = OnceCell::new();
 
 impl Runtime {
+    /// Creates a new Runtime.
+    ///
+    /// `threads` is the amount of worker threads, ignored for `Flavor::CurrentThread`.
+    pub fn new(flavor: Flavor, threads: usize) -> Runtime {
+        #[cfg(feature = "rt-multi-thread")]
+        let pool = match flavor {
+            Flavor::CurrentThread => None,
+            Flavor::MultiThread => Some(Mutex::new(ThreadPool::new(threads))),
+        };
+        #[cfg(not(feature = "rt-multi-thread"))]
+        let _ = (flavor, threads);
+
+        Runtime {
+            shared: Arc::new(Shared {
+                queue: Queue::new(),
+                #[cfg(feature = "rt-multi-thread")]
+                pool,
+                tasks: Mutex::new(Slab::new()),
+                #[cfg(feature = "rt-drive-io")]
+                parked: AtomicBool::new(false),
+            }),
+        }
+    }
+
     /// Builds the global Runtime instance.
+    #[cfg(feature = "rt-multi-thread")]
     pub fn build(threads: usize) -> &'static Runtime {
-        // Sender and Receiver channel for the Tasks
-        let (sd, rc) = mpmc::channel();
-
         // Acquires the reference to the OnceCell<T> in the static variable
         // and initializes it with the Runtime
-        RUNTIME.get_or_init_blocking(|| {
-            // Driver and it's handle
-            let driver = Reactor::new();
-
-            // Runtime
-            Runtime {
-                receiver: rc,
-                sender: sd,
-
-                driver: driver.0,
-                handle: Arc::clone(&driver.1),
-
-                pool: Mutex::new(ThreadPool::new(threads)),
-            }
-        })
+        RUNTIME.get_or_init_blocking(|| Runtime::new(Flavor::MultiThread, threads))
     }
 
-    /// Obtains a immutable reference to the Runtime.
+    /// Obtains a immutable reference to the global Runtime.
     ///
     /// Panics if there is no runtime present.
     pub fn get() -> &'static Runtime {
         RUNTIME.get().expect("There is no runtime available!")
     }
 
-    /// Initializes the runtime with a Future created from the `main` function.
+    /// Initializes the global runtime with a Future created from the `main` function.
     pub fn init<F>(future: F)
     where
         F: Future<Output = ()> + Send + 'static,
     {
-        let sender = &Runtime::get().sender;
-        let task = Task::arc_new(future, sender.clone());
+        Runtime::get().block_on(future)
+    }
+
+    /// Runs a future to completion on this runtime, polling the tasks woken meanwhile.
+    pub fn block_on<F, T: 'static>(&self, future: F) -> T
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        match self.run(future, None) {
+            Ok(output) => output,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Same as `Runtime::block_on`, but gives up once `timeout` has passed.
+    ///
+    /// The returned `Elapsed` holds a task dump showing what the tasks were waiting on.
+    pub fn block_on_timeout<F, T: 'static>(
+        &self,
+        future: F,
+        timeout: Duration,
+    ) -> Result<T, Elapsed>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.run(future, Some(Instant::now() + timeout))
+    }
+
+    fn run<F, T: 'static>(&self, future: F, deadline: Option<Instant>) -> Result<T, Elapsed>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let _enter = Shared::enter(Arc::clone(&self.shared));
 
-        sender.send(task).expect("failed to initialize runtime");
+        // The main task always goes to our own queue, so only this thread polls it,
+        // which is also why its output does not need to be `Send`.
+        // It is kept alive here even when nothing else holds a waker to it.
+        let join = JoinState::arc_new();
+        let main_task = Task::arc_new(future, Arc::clone(&join), &self.shared, true);
+        let mut handle = TaskHandle::new(join);
 
-        let receiver = &Runtime::get().receiver;
+        main_task.send();
+
+        while !handle.is_finished() {
+            match self.shared.pop_blocking(deadline) {
+                Some(task) => task.poll(),
+                None => return Err(Elapsed { dump: self.dump() }),
+            }
+        }
+
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(&mut handle).poll(&mut cx) {
+            Poll::Ready(output) => Ok(output),
+            Poll::Pending => unreachable!("finished task handle was pending"),
+        }
+    }
+
+    /// Same as `Runtime::block_on`, for futures that are not `Send` or not `'static`.
+    ///
+    /// The future is polled right here on the calling thread, whatever the flavor,
+    /// the tasks it spawns still have to be `Send`.
+    pub fn block_on_local<F: Future>(&self, future: F) -> F::Output {
+        match self.run_local(future, None) {
+            Ok(output) => output,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Same as `Runtime::block_on_local`, but gives up once `timeout` has passed,
+    /// see `Runtime::block_on_timeout`.
+    pub fn block_on_local_timeout<F: Future>(
+        &self,
+        future: F,
+        timeout: Duration,
+    ) -> Result<F::Output, Elapsed> {
+        self.run_local(future, Some(Instant::now() + timeout))
+    }
+
+    fn run_local<F: Future>(
+        &self,
+        future: F,
+        deadline: Option<Instant>,
+    ) -> Result<F::Output, Elapsed> {
+        let _enter = Shared::enter(Arc::clone(&self.shared));
+        let mut future = std::pin::pin!(future);
+
+        // Stands in for the future in the queue: its waker is the future's,
+        // and popping it means the future was woken.
+        let signal = Task::arc_new(BlockOn, JoinState::arc_new(), &self.shared, true);
+        let waker = task::waker(Arc::clone(&signal));
+        let mut cx = Context::from_waker(&waker);
+
+        signal.send();
         loop {
-            let task = match receiver.recv() {
-                Ok(t) => t,
-                Err(e) => panic!("{}", e),
+            let task = match self.shared.pop_blocking(deadline) {
+                Some(task) => task,
+                None => return Err(Elapsed { dump: self.dump() }),
             };
 
+            let woken = Arc::ptr_eq(&task, &signal);
+            task.poll();
+            if woken {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return Ok(output);
+                }
+            }
+        }
+    }
+
+    /// Polls the tasks of this runtime that are ready to make progress, until there are none left.
+    ///
+    /// Never blocks, it is meant for driving the runtime from a foreign event loop.
+    /// Once the queue ran dry, the reactor is turned without blocking for the tasks its
+    /// pending events wake, unless a polling thread holds it, see `Reactor::turn`.
+    ///
+    /// Returns the amount of tasks polled.
+    pub fn run_until_stalled(&self) -> usize {
+        let _enter = Shared::enter(Arc::clone(&self.shared));
+        let mut polled = 0;
+
+        while let Some(task) = self.shared.queue.pop().or_else(|| self.shared.pop_woken()) {
             task.poll();
+            polled += 1;
+        }
+
+        polled
+    }
+
+    /// Takes a snapshot of every live task of this runtime.
+    pub fn dump(&self) -> TaskDump {
+        let tasks: Vec<Arc<Task>> = self
+            .shared
+            .tasks
+            .lock()
+            .expect("failed task registry lock")
+            .iter()
+            .filter_map(|(_, task)| task.upgrade())
+            .collect();
+
+        TaskDump {
+            tasks: tasks.iter().map(|task| task.info()).collect(),
         }
     }
 
-    /// Spawns a task onto the Runtime.
-    pub fn spawn<F, T: 'static>(future: F) -> TaskHandle<T>
+    /// Spawns a task onto the current Runtime.
+    ///
+    /// The current Runtime is the one polling the caller,
+    /// or the global one outside of any Runtime.
+    pub fn spawn<F, T: Send + 'static>(future: F) -> TaskHandle<T>
     where
         F: Future<Output = T> + Send + 'static,
     {
-        let sender = Runtime::get().sender.clone();
-        let task = Task::arc_new(future, sender);
-        let handle = TaskHandle::new(Arc::downgrade(&task));
-
-        match Runtime::send_task(task) {
-            Ok(()) => {}
-            Err(e) => panic!("{e}"),
-        };
+        Shared::current().spawn(future)
+    }
 
-        handle
+    /// Spawns a task onto this Runtime.
+    ///
+    /// Unlike `Runtime::spawn`, it does not matter which runtime is polling the caller.
+    pub fn spawn_on<F, T: Send + 'static>(&self, future: F) -> TaskHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.shared.spawn(future)
     }
 
     /// Register device in the I/O Reactor's registry
     /// Essentially it is just `Reactor::register`
-    pub fn register(dev: &mut impl Source, interest: Interest) -> IoResult<()> {
+    pub fn register(dev: &mut impl Source, interest: Interest) -> IoResult<Token> {
         Reactor::register(dev, interest)
     }
 
     /// Reregister device in the I/O Reactor's registry
     /// Essentially it is just `Reactor::reregister`
-    pub fn reregister(src: &mut impl Source, token: usize, interest: Interest) -> IoResult<()> {
+    pub fn reregister(src: &mut impl Source, token: Token, interest: Interest) -> IoResult<()> {
         Reactor::reregister(src, token, interest)
     }
 
-    /// Get registry.
-    pub fn registry() -> &'static Registry {
-        Runtime::get().handle.registry()
+    /// Deregister device from the I/O Reactor's registry
+    /// Essentially it is just `Reactor::deregister`
+    pub fn deregister(src: &mut impl Source, token: Token) -> IoResult<()> {
+        Reactor::deregister(src, token)
     }
 
-    /// Send a task to the thread pool.
-    fn send_task(task: Arc<Task>) -> IoResult<()> {
-        Runtime::get()
-            .pool
-            .lock()
-            .expect("Failed lock on mutex containing the thread pool")
-            .distribute_task(task)
+    /// Get registry.
+    pub fn registry() -> &'static Registry {
+        Reactor::registry()
     }
 }
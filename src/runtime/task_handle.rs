@@ -1,35 +1,84 @@
-use crate::runtime::runtime::Task;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Weak;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Waker};
 
-pub struct TaskHandle {
-    task: Weak<Task>,
+/// State shared between a spawned task and its `TaskHandle`.
+pub(crate) struct JoinState<T> {
+    /// Output of the task, once it finished.
+    output: Option<T>,
+
+    /// Waker of the task awaiting the handle.
+    waker: Option<Waker>,
+
+    /// Set when the task was dropped without completing.
+    cancelled: bool,
+}
+
+impl<T> JoinState<T> {
+    pub(crate) fn arc_new() -> Arc<Mutex<JoinState<T>>> {
+        Arc::new(Mutex::new(JoinState {
+            output: None,
+            waker: None,
+            cancelled: false,
+        }))
+    }
+
+    /// Stores the output of the task and wakes the awaiting task.
+    pub(crate) fn complete(&mut self, output: T) {
+        self.output = Some(output);
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
+
+    /// Marks the task as dropped and wakes the awaiting task.
+    pub(crate) fn cancel(&mut self) {
+        self.cancelled = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
 }
 
-impl TaskHandle {
-    // No-operation context just to satisfy the `Future` trait.
-    const CONTEXT_NOOP: Context<'_> = Context::from_waker(Waker::noop());
+/// Handle to a spawned task, resolves to the output of the task.
+///
+/// Panics when awaited if the task was dropped before completing,
+/// which happens when the runtime it was spawned on is dropped.
+pub struct TaskHandle<T> {
+    join: Arc<Mutex<JoinState<T>>>,
+}
 
-    pub(crate) fn new(task: Weak<Task>) -> TaskHandle {
-        TaskHandle { task }
+impl<T> TaskHandle<T> {
+    pub(crate) fn new(join: Arc<Mutex<JoinState<T>>>) -> TaskHandle<T> {
+        TaskHandle { join }
+    }
+
+    /// Checks if the task finished, without consuming its output.
+    pub fn is_finished(&self) -> bool {
+        let join = self.join.lock().expect("failed join state lock");
+        join.output.is_some() || join.cancelled
     }
 }
 
-impl Future for TaskHandle {
-    type Output = ();
+impl<T> Future for TaskHandle<T> {
+    type Output = T;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let arc_task = match self.task.upgrade() {
-            Some(a) => a,
-            None => return Poll::Ready(()),
-        };
-
-        if !arc_task.ready() {
-            arc_task.change_waker(cx.waker());
-            Poll::Pending
-        } else {
-            Poll::Ready(())
+        let mut join = self.join.lock().expect("failed join state lock");
+
+        if let Some(output) = join.output.take() {
+            return Poll::Ready(output);
         }
+
+        if join.cancelled {
+            panic!("Awaited a task that was dropped before completing!");
+        }
+
+        match join.waker {
+            Some(ref waker) if waker.will_wake(cx.waker()) => {}
+            _ => join.waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
     }
 }
@@ -1,6 +1,7 @@
 use crate::runtime::{runtime::Task, WorkerThread};
 use slab::Slab;
-use std::io::Result as IoResult;
+use std::io::{Error as IoError, Result as IoResult};
+use std::sync::mpsc::SendError;
 use std::sync::Arc;
 
 pub struct ThreadPool {
@@ -17,7 +18,7 @@ impl ThreadPool {
 
     /// This function will attempt to distribute tasks across the `WorkerThread`s
     /// An error returned from this function is probably very critical as it is related to
-    /// a problem with recreating a thread.
+    /// a problem with recreating a thread, or with handing the task to the new one.
     pub fn distribute_task(&mut self, task: Arc<Task>) -> IoResult<()> {
         let mut amnt_key = (0, 0);
 
@@ -39,23 +40,13 @@ impl ThreadPool {
             .get_mut(amnt_key.1)
             .expect("This should NOT happen.");
 
-        match thread.send(task) {
-            Ok(()) => {
-                println!(
-                    "Sending task to thread: {} with count of {}",
-                    thread.get_name(),
-                    thread.get_amount_sent()
-                );
-            }
-            Err(e) => {
-                // log the error ofc
-                println!("Error while sending to {0}: {1}", thread.get_name(), e);
-
-                // if this throws an error
-                // it's seriously bad
-                thread.recreate_thread()?;
-            }
-        };
+        if let Err(SendError(task)) = thread.send(task) {
+            // The worker is gone, its replacement gets the task.
+            thread.recreate_thread()?;
+            thread.send(task).map_err(|_| {
+                IoError::other(format!("worker {} stopped taking tasks", thread.get_name()))
+            })?;
+        }
 
         Ok(())
     }
@@ -1,14 +1,29 @@
+use crate::io::Reactor;
 use crate::runtime::runtime::Task;
 use slab::Slab;
+#[cfg(feature = "rt-drive-io")]
+use std::cell::RefCell;
+#[cfg(feature = "rt-drive-io")]
+use std::collections::VecDeque;
 use std::io;
 use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::AtomicBool;
 use std::sync::{mpsc, Arc};
 use std::thread::{self, available_parallelism};
 
+#[cfg(feature = "rt-drive-io")]
+thread_local! {
+    // Tasks woken on this worker thread, polled by it before the ones sent to it.
+    static LOCAL: RefCell<Option<VecDeque<Arc<Task>>>> = const { RefCell::new(None) };
+}
+
 /// Describes a thread of the Runtime.
 pub struct WorkerThread {
     name: Option<String>,
     sender: mpsc::Sender<Arc<Task>>,
+    /// Set while the thread waits for tasks in the reactor's poll.
+    #[cfg_attr(not(feature = "rt-drive-io"), allow(dead_code))]
+    parked: Arc<AtomicBool>,
     amount: usize,
     id: usize,
     active: bool,
@@ -30,6 +45,7 @@ impl WorkerThread {
     /// Creates one `WorkerThread`
     pub(crate) fn new(id: usize, name: Option<String>) -> io::Result<WorkerThread> {
         let (sender, recv) = mpsc::channel::<Arc<Task>>();
+        let parked = Arc::new(AtomicBool::new(false));
 
         let _thread = if let Some(ref n) = name {
             thread::Builder::new().name(n.clone())
@@ -37,15 +53,13 @@ impl WorkerThread {
             thread::Builder::new()
         };
 
-        _thread.spawn(move || {
-            while let Ok(task) = recv.recv() {
-                task.poll();
-            }
-        })?;
+        let arc_parked = Arc::clone(&parked);
+        _thread.spawn(move || WorkerThread::work(id, recv, arc_parked))?;
 
         Ok(WorkerThread {
             name,
             sender,
+            parked,
             amount: 0,
             id,
             active: true,
@@ -83,34 +97,118 @@ impl WorkerThread {
     /// Use only if your thread panicked.
     pub(crate) fn recreate_thread(&mut self) -> io::Result<()> {
         let (sender, recv) = mpsc::channel::<Arc<Task>>();
+        let parked = Arc::new(AtomicBool::new(false));
 
         let name = self
             .name
             .as_ref()
             .map_or("unnamed".to_string(), |s| s.clone());
 
-        let _thread = thread::Builder::new().name(name).spawn(move || {
-            while let Ok(task) = recv.recv() {
-                task.poll();
-            }
-        })?;
+        let arc_parked = Arc::clone(&parked);
+        let id = self.id;
+        let _thread = thread::Builder::new()
+            .name(name)
+            .spawn(move || WorkerThread::work(id, recv, arc_parked))?;
 
         self.sender = sender;
+        self.parked = parked;
         self.amount = 0;
 
         Ok(())
     }
 
+    /// Polls the tasks sent to the thread, until its sender is dropped.
+    #[cfg(not(feature = "rt-drive-io"))]
+    fn work(id: usize, recv: mpsc::Receiver<Arc<Task>>, _parked: Arc<AtomicBool>) {
+        Reactor::set_worker(id);
+
+        while let Ok(task) = recv.recv() {
+            task.poll();
+        }
+    }
+
+    /// Polls the tasks woken on the thread and the ones sent to it, until its sender is dropped.
+    ///
+    /// Once there is nothing left, the thread waits in the reactor's poll,
+    /// unless another thread is already, then it waits for a task to be sent.
+    #[cfg(feature = "rt-drive-io")]
+    fn work(id: usize, recv: mpsc::Receiver<Arc<Task>>, parked: Arc<AtomicBool>) {
+        Reactor::set_worker(id);
+        LOCAL.with(|local| *local.borrow_mut() = Some(VecDeque::new()));
+
+        loop {
+            // Not polled inside `with`, polling pushes into the local queue.
+            while let Some(task) = LOCAL.with(|local| local.borrow_mut().as_mut()?.pop_front()) {
+                task.poll();
+            }
+
+            match recv.try_recv() {
+                Ok(task) => {
+                    task.poll();
+                    continue;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => break,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            let mut disconnected = false;
+            let idle = || match recv.try_recv() {
+                Ok(task) => {
+                    WorkerThread::push_local(&task);
+                    false
+                }
+                Err(mpsc::TryRecvError::Empty) => true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    disconnected = true;
+                    false
+                }
+            };
+
+            if Reactor::drive(None, &parked, idle) {
+                if disconnected {
+                    break;
+                }
+                continue;
+            }
+
+            match recv.recv() {
+                Ok(task) => task.poll(),
+                Err(_) => break,
+            }
+        }
+
+        LOCAL.with(|local| local.borrow_mut().take());
+    }
+
+    /// Pushes a woken task into the local queue, if this is a worker thread.
+    ///
+    /// Returns `false` if it is not.
+    #[cfg(feature = "rt-drive-io")]
+    pub(crate) fn push_local(task: &Arc<Task>) -> bool {
+        LOCAL.with(|local| match local.borrow_mut().as_mut() {
+            Some(queue) => {
+                queue.push_back(Arc::clone(task));
+                true
+            }
+            None => false,
+        })
+    }
+
     /// Checks if the specified amount of would-be used threads is possible to execute.
     pub fn ok_thread_amount(amount: usize) -> bool {
         available_parallelism().unwrap().get() >= amount
     }
 
     /// Send a `Arc<Task>` to the `WorkerThread`.
+    ///
+    /// Interrupts the thread if it waits in the reactor's poll.
     pub fn send(&mut self, task: Arc<Task>) -> Result<(), mpsc::SendError<Arc<Task>>> {
         self.sender.send(task)?;
         self.amount += 1;
 
+        #[cfg(feature = "rt-drive-io")]
+        Reactor::unpark(&self.parked);
+
         Ok(())
     }
 
@@ -0,0 +1,5 @@
+// Re-exported from `async-lock`, which the runtime already depends on.
+pub use async_lock::{
+    Barrier, BarrierWaitResult, Mutex, MutexGuard, OnceCell, RwLock, RwLockReadGuard,
+    RwLockWriteGuard, Semaphore, SemaphoreGuard,
+};
@@ -0,0 +1,65 @@
+#![cfg(all(unix, feature = "rt-drive-io", feature = "net"))]
+
+use apple::io::AsyncReadExt;
+use apple::net::UnixStream;
+use apple::runtime::{Flavor, Runtime};
+use std::io::Write;
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Stream registered in the reactor, and a blocking peer for other threads to write into.
+fn pair() -> (UnixStream, StdUnixStream) {
+    let (a, b) = StdUnixStream::pair().unwrap();
+    (UnixStream::from_std(a).unwrap(), b)
+}
+
+/// Writes `buf` into `peer` from another thread, once the runtime had time to go idle.
+fn write_later(mut peer: StdUnixStream, buf: &'static [u8]) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        peer.write_all(buf).unwrap();
+    })
+}
+
+#[test]
+fn block_on_wakes_from_the_reactor_poll() {
+    let runtime = Runtime::new(Flavor::CurrentThread, 1);
+    let (mut stream, peer) = pair();
+    let writer = write_later(peer, b"ping");
+
+    // Nothing else polls the reactor, `block_on` has to while it waits.
+    let buf = runtime
+        .block_on_timeout(
+            async move {
+                let mut buf = [0u8; 4];
+                stream.read_exact(&mut buf).await.unwrap();
+                buf
+            },
+            Duration::from_secs(5),
+        )
+        .expect("read was never woken");
+    assert_eq!(&buf, b"ping");
+    writer.join().unwrap();
+}
+
+#[cfg(feature = "rt-multi-thread")]
+#[test]
+fn idle_worker_wakes_from_the_reactor_poll() {
+    let runtime = Runtime::new(Flavor::MultiThread, 1);
+    let (mut stream, peer) = pair();
+
+    let read = runtime.spawn_on(async move {
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+    });
+    let writer = write_later(peer, b"pong");
+
+    // Not awaited, so only the worker, parked in the reactor's poll, can see the data.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !read.is_finished() {
+        assert!(Instant::now() < deadline, "read was never woken");
+        thread::sleep(Duration::from_millis(10));
+    }
+    writer.join().unwrap();
+}
@@ -0,0 +1,52 @@
+#![cfg(unix)]
+
+use apple::io::{AsyncReadExt, AsyncWriteExt, Reactor};
+use apple::net::UnixStream;
+use apple::runtime::{Flavor, Runtime};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// Waits for the fd of the reactor to become readable, like a foreign event loop would.
+fn wait_readable(fd: i32) {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Safety: `pollfd` is valid for the duration of the call.
+    let ready = unsafe { libc::poll(&mut pollfd, 1, 5000) };
+    assert_eq!(ready, 1, "the reactor fd did not become readable");
+}
+
+#[test]
+fn foreign_loop_drives_the_reactor() {
+    // Checking for a failure must not start the reactors, or they could not be set up anymore.
+    assert!(Reactor::failure().is_none());
+    Reactor::init_embedded().unwrap();
+    assert!(Reactor::init_embedded().is_err());
+
+    let runtime = Runtime::new(Flavor::CurrentThread, 1);
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+    let reader = runtime.spawn_on(async move {
+        let mut buf = [0u8; 5];
+        a.read_exact(&mut buf).await.unwrap();
+        buf
+    });
+
+    // The reader runs until it waits for the socket.
+    assert_eq!(runtime.run_until_stalled(), 1);
+    assert_eq!(Reactor::turn(Some(Duration::ZERO)).unwrap(), 0);
+
+    let writer = runtime.spawn_on(async move { b.write_all(b"hello").await.unwrap() });
+    runtime.run_until_stalled();
+
+    let fd = Reactor::get_handle().as_raw_fd();
+    while !reader.is_finished() {
+        wait_readable(fd);
+        Reactor::turn(Some(Duration::ZERO)).unwrap();
+        runtime.run_until_stalled();
+    }
+
+    assert!(writer.is_finished());
+    assert_eq!(runtime.block_on(reader), *b"hello");
+}
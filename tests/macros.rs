@@ -0,0 +1,56 @@
+use apple::runtime::Runtime;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[apple::main(worker_threads = 1)]
+async fn multi_thread_main() -> usize {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let count = Arc::clone(&count);
+            Runtime::spawn(async move { count.fetch_add(1, Ordering::Relaxed) })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await;
+    }
+    count.load(Ordering::Relaxed)
+}
+
+#[apple::main(flavor = "current_thread", crate = "apple")]
+async fn current_thread_main() -> &'static str {
+    Runtime::spawn(async { "spawned" }).await
+}
+
+#[test]
+fn main_runs_the_body() {
+    assert_eq!(multi_thread_main(), 4);
+    assert_eq!(current_thread_main(), "spawned");
+}
+
+#[apple::test]
+async fn test_runs_the_body() {
+    let value = Runtime::spawn(async { 1 + 1 }).await;
+    assert_eq!(value, 2);
+}
+
+#[apple::test(flavor = "multi_thread", worker_threads = 1, timeout = 5)]
+async fn test_on_multi_thread() {
+    let value = Runtime::spawn(async { 2 * 3 }).await;
+    assert_eq!(value, 6);
+}
+
+#[apple::test(timeout_ms = 100)]
+#[should_panic(expected = "test timed out after 100ms\nTask dump (1 live tasks):")]
+async fn hung_test_times_out() {
+    std::future::pending::<()>().await
+}
+
+#[apple::test(timeout = 5)]
+async fn test_body_does_not_need_send() {
+    // Held across an await, so the body is not `Send`.
+    let local = std::rc::Rc::new(1);
+    let value = Runtime::spawn(async { 2 }).await;
+    assert_eq!(*local + value, 3);
+}
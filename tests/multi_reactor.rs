@@ -0,0 +1,14 @@
+#![cfg(not(feature = "rt-drive-io"))]
+
+use apple::io::{Placement, Reactor};
+use std::io::ErrorKind;
+use std::time::Duration;
+
+#[test]
+fn turn_refuses_several_reactors() {
+    Reactor::init(2, Placement::RoundRobin).unwrap();
+    assert_eq!(Reactor::all().len(), 2);
+
+    let e = Reactor::turn(Some(Duration::ZERO)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Unsupported);
+}
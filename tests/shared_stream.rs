@@ -0,0 +1,45 @@
+#![cfg(unix)]
+
+use apple::io::{AsyncReadExt, AsyncWriteExt};
+use apple::net::UnixStream;
+use apple::runtime::{Flavor, Runtime};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn tasks_sharing_a_stream_are_all_woken() {
+    let runtime = Runtime::new(Flavor::CurrentThread, 1);
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let a = Arc::new(a);
+
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let a = Arc::clone(&a);
+            runtime.spawn_on(async move {
+                let mut buf = [0u8; 1];
+                (&*a).read_exact(&mut buf).await.unwrap();
+                buf[0]
+            })
+        })
+        .collect();
+
+    // Both readers wait on the socket before anything is written.
+    assert_eq!(runtime.run_until_stalled(), 2);
+
+    let mut read = runtime
+        .block_on_timeout(
+            async move {
+                b.write_all(b"ab").await.unwrap();
+                let mut read = Vec::new();
+                for reader in readers {
+                    read.push(reader.await);
+                }
+                read
+            },
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+    read.sort_unstable();
+    assert_eq!(read, b"ab");
+}